@@ -0,0 +1,54 @@
+use anyhow::Result;
+use regnumassets::asset::sound::repair;
+use regnumassets::asset::OggReport;
+use regnumassets::{AssetContent, AssetData, AssetType, ResourceIndex};
+use std::fs::File;
+
+fn main() -> Result<()> {
+    // pass --trim to export repaired copies of the broken sounds
+    let trim = std::env::args().any(|arg| arg == "--trim");
+
+    let f = File::open("examples/regnum/data2.idx")?;
+    let index = ResourceIndex::read(f).unwrap();
+
+    let f = File::open("examples/regnum/data2.sdb")?;
+    let sounds = index.filter_by_type(AssetType::Sound);
+
+    for sound in &sounds {
+        let AssetContent::Sound {
+            filename, bytes, ..
+        } = AssetData::read(&f, sound)?.content
+        else {
+            continue;
+        };
+
+        let report = OggReport::check(&bytes);
+        if report.is_valid() {
+            continue;
+        }
+
+        println!(
+            "Resource #{}: {} ({} pages, {} of {} bytes valid)",
+            sound.resource_id.unwrap_or(0),
+            filename,
+            report.pages,
+            report.valid_length,
+            bytes.len()
+        );
+        for issue in &report.issues {
+            println!("  {:?}", issue);
+        }
+
+        if trim {
+            // keep the file name only, so the copy stays in the examples folder
+            let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+            if name.is_empty() || name == "." || name == ".." {
+                println!("  skipped, invalid file name");
+                continue;
+            }
+            std::fs::write(format!("examples/{}", name), repair(&bytes))?;
+        }
+    }
+
+    Ok(())
+}
@@ -17,6 +17,7 @@ fn main() -> Result<()> {
             filename,
             bytes,
             size,
+            ..
         } => {
             println!("writing {} bytes file to {}", size, filename);
             let mut output = File::create(filename)?;
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use regnumassets::asset::sound::PcmAudio;
+use regnumassets::{AssetContent, AssetData, ResourceIndex};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+fn main() -> Result<()> {
+    let f = File::open("examples/regnum/data2.idx")?;
+    let index = ResourceIndex::read(f).unwrap();
+
+    let sound = index.get_by_resource_id(50677).unwrap();
+
+    let f = File::open("examples/regnum/data2.sdb")?;
+    let asset = AssetData::read(&f, &sound).unwrap();
+
+    match asset.content {
+        AssetContent::Sound {
+            filename, bytes, ..
+        } => {
+            let audio = PcmAudio::decode(&bytes)?;
+            let filename = Path::new(&filename).with_extension("wav");
+            println!(
+                "writing {:.2}s of audio to {}",
+                audio.duration().as_secs_f64(),
+                filename.display()
+            );
+
+            let mut output = BufWriter::new(File::create(filename)?);
+            audio.write_wav(&mut output)?;
+            output.flush()?;
+        }
+        _ => {
+            println!("could not parse sound asset")
+        }
+    }
+
+    Ok(())
+}
@@ -13,7 +13,9 @@ fn main() -> Result<()> {
     let asset = AssetData::read(&f, &texture).unwrap();
 
     match asset.content {
-        AssetContent::Texture { width, height, dds } => {
+        AssetContent::Texture {
+            width, height, dds, ..
+        } => {
             println!(
                 "writing texture '{}' ({}x{}) to out.dds",
                 asset.asset_name, width, height
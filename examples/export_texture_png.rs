@@ -0,0 +1,35 @@
+use anyhow::Result;
+use regnumassets::asset::texture::RgbaImage;
+use regnumassets::{AssetContent, AssetData, ResourceIndex};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+fn main() -> Result<()> {
+    let f = File::open("examples/regnum/data6.idx")?;
+    let index = ResourceIndex::read(f).unwrap();
+
+    let texture = index.get_by_resource_id(85953).unwrap();
+
+    let f = File::open("examples/regnum/data6.sdb")?;
+    let asset = AssetData::read(&f, &texture).unwrap();
+
+    match asset.content {
+        AssetContent::Texture { dds, .. } => {
+            let image = RgbaImage::from_dds(&dds)?;
+            println!(
+                "writing texture '{}' ({}x{}) to out.png",
+                asset.asset_name, image.width, image.height
+            );
+
+            let mut output = BufWriter::new(File::create("out.png")?);
+            image.write_png(&mut output)?;
+            output.flush()?;
+        }
+
+        _ => {
+            println!("could not parse texture asset")
+        }
+    }
+
+    Ok(())
+}
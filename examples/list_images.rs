@@ -12,7 +12,7 @@ fn main() -> Result<()> {
         println!(
             "Resource #{}: {}",
             image.resource_id.unwrap_or(0),
-            image.name.as_deref().unwrap_or("(unnamed)".into()),
+            image.name.as_deref().unwrap_or("(unnamed)"),
         );
     }
 
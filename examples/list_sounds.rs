@@ -1,19 +1,46 @@
 use anyhow::Result;
-use regnumassets::{AssetType, ResourceIndex};
+use regnumassets::asset::VorbisInfo;
+use regnumassets::{AssetContent, AssetData, AssetType, ResourceIndex};
 use std::fs::File;
 
 fn main() -> Result<()> {
     let f = File::open("examples/regnum/data2.idx")?;
     let index = ResourceIndex::read(f).unwrap();
 
+    let f = File::open("examples/regnum/data2.sdb")?;
     let sounds = index.filter_by_type(AssetType::Sound);
 
     for sound in &sounds {
-        println!(
-            "Resource #{}: {}",
-            sound.resource_id.unwrap_or(0),
-            sound.name.as_deref().unwrap_or("(unnamed)".into())
-        );
+        let name = sound.name.as_deref().unwrap_or("(unnamed)");
+        let info = match AssetData::read(&f, sound)?.content {
+            AssetContent::Sound { bytes, .. } => VorbisInfo::parse(&bytes),
+            _ => {
+                println!(
+                    "Resource #{}: {} (not parsed)",
+                    sound.resource_id.unwrap_or(0),
+                    name
+                );
+                continue;
+            }
+        };
+
+        match info {
+            Ok(info) => println!(
+                "Resource #{}: {} ({} ch, {} Hz, {} kbps, {:.2}s)",
+                sound.resource_id.unwrap_or(0),
+                name,
+                info.channels,
+                info.sample_rate,
+                info.bitrate_nominal / 1000,
+                info.duration().as_secs_f64()
+            ),
+            Err(err) => println!(
+                "Resource #{}: {} (broken file: {})",
+                sound.resource_id.unwrap_or(0),
+                name,
+                err
+            ),
+        }
     }
 
     Ok(())
@@ -12,7 +12,7 @@ fn main() -> Result<()> {
         println!(
             "Resource #{}: {}",
             text.resource_id.unwrap_or(0),
-            text.name.as_deref().unwrap_or("(unnamed)".into())
+            text.name.as_deref().unwrap_or("(unnamed)")
         );
     }
 
@@ -12,7 +12,7 @@ fn main() -> Result<()> {
         println!(
             "Resource #{}: {}",
             texture.resource_id.unwrap_or(0),
-            texture.name.as_deref().unwrap_or("(unnamed)".into()),
+            texture.name.as_deref().unwrap_or("(unnamed)"),
         );
     }
 
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use regnumassets::{AssetData, AssetType, DatabaseWriter, ResourceIndex};
+use std::fs::File;
+use std::io::BufWriter;
+
+fn main() -> Result<()> {
+    let f = File::open("examples/regnum/data2.idx")?;
+    let index = ResourceIndex::read(f).unwrap();
+
+    let f = File::open("examples/regnum/data2.sdb")?;
+    let output = BufWriter::new(File::create("out.sdb")?);
+    let mut database = DatabaseWriter::new(output)?;
+
+    // keep block headers and payloads as found
+    for sound in index.filter_by_type(AssetType::Sound) {
+        let asset = AssetData::read(&f, &sound).unwrap();
+        let payload = AssetData::read_raw(&f, &sound)?;
+        let block_header = AssetData::read_block_header(&f, &sound)?;
+        database.add_with_block_header(&asset, &payload, &block_header)?;
+    }
+
+    // keep the header values of the original index
+    let index = database.finish_with(index.unknown_1, index.unknown_2)?;
+    println!("writing {} sounds to out.sdb", index.bookmarks().len());
+
+    let output = BufWriter::new(File::create("out.idx")?);
+    index.write(output)?;
+
+    Ok(())
+}
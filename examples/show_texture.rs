@@ -0,0 +1,30 @@
+use anyhow::Result;
+use regnumassets::{AssetData, ResourceIndex};
+use std::fs::File;
+
+fn main() -> Result<()> {
+    let f = File::open("examples/regnum/data6.idx")?;
+    let index = ResourceIndex::read(f).unwrap();
+
+    let texture = index.get_by_resource_id(85953).unwrap();
+
+    let f = File::open("examples/regnum/data6.sdb")?;
+    let asset = AssetData::read(&f, &texture).unwrap();
+
+    println!("Texture '{}'", asset.asset_name);
+    for surface in asset.content.surfaces()? {
+        println!(
+            "  layer {} face {:?} level {}: {}x{} {:?} (bytes {}..{})",
+            surface.layer,
+            surface.face,
+            surface.level,
+            surface.width,
+            surface.height,
+            surface.format,
+            surface.range.start,
+            surface.range.end
+        );
+    }
+
+    Ok(())
+}
@@ -3,11 +3,21 @@ use super::AssetType;
 /// An wrapper struct pointing to a specific asset in a database file
 #[derive(Debug, Clone)]
 pub struct AssetBookmark {
+    /// A string with the form 'resource_...'
+    pub uid: String,
     pub resource_id: Option<u32>,
     pub asset_type: AssetType,
     pub name: Option<String>,
+    /// The name as stored in the index, usually with the form 'TYPE::NAME'
+    pub resource_name: String,
     pub node_start: usize,
     pub node_end: usize,
     pub node_next: usize,
+    pub node_previous: usize,
+    pub node_type: u32,
+    /// The start position stored in the index item
+    pub item_start: usize,
+    /// An item value of unknown meaning
+    pub item_unknown: u32,
     pub size: u32,
 }
@@ -1,25 +1,45 @@
 use super::WINDOWS_SEPARATOR;
 use crate::asset::image::{find_last_sequence, JPEG_END};
-use crate::asset::sound::{SOUND_ATTR_FILEINBUFFER, SOUND_ATTR_FILENAME};
+use crate::asset::property::{Property, PropertyBag, PropertyRef, PropertyValue};
+use crate::asset::sound::{
+    SOUND_ATTR_FILEINBUFFER, SOUND_ATTR_FILENAME, SOUND_FLAGS, SOUND_SIZE_TOLERANCE,
+};
 use crate::asset::text::{parse_text, TextContent};
-use crate::{asset::ASSET_NODE_END, errors::AssetErrors, AssetBookmark, AssetType};
-use anyhow::{anyhow, Result};
-use byteorder::{LittleEndian, ReadBytesExt};
+use crate::asset::texture::header::{
+    TEXTURE_HEADER_LENGTH, TEXTURE_SETTINGS_LENGTH, TEXTURE_SETTINGS_MARKER,
+};
+use crate::asset::texture::{TextureHeader, TextureSurface};
+use crate::errors::{AssetErrors, Result};
+use crate::parser::{array, fail, magic, utf8_string, windows_1252, SliceErrorKind, SliceResult};
+use crate::reader::OffsetReader;
+use crate::{asset::ASSET_NODE_END, AssetBookmark, AssetType};
+use byteorder::{LittleEndian, WriteBytesExt};
 use ddsfile::Dds;
 use encoding_rs::WINDOWS_1252;
-use std::io::{Read, Seek};
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u32, le_u8};
+use nom::Parser;
+use std::io::{ErrorKind, Read, Seek, Write};
 
 /// An enum listing all supported content types found on a database file
 #[derive(Debug)]
 pub enum AssetContent {
     /// A variant holding a OGG file
+    ///
+    /// Attributes other than the filename and the file are kept in `properties`.
     Sound {
         filename: String,
         size: u32,
         bytes: Vec<u8>,
+        properties: PropertyBag,
+    },
+    /// A variant holding a Direct Draw Surface, along with the values found before it
+    Texture {
+        width: u32,
+        height: u32,
+        dds: Dds,
+        header: Box<TextureHeader>,
     },
-    /// A variant holding a Direct Draw Surface
-    Texture { width: u32, height: u32, dds: Dds },
     /// A variant holding a list of text components
     Text { contents: Vec<TextContent> },
     /// A variant holding a JPEG image
@@ -31,26 +51,23 @@ pub enum AssetContent {
 impl AssetContent {
     /// Tries to convert a value to a variant of AssetContent
     pub fn read<T: Read + Seek>(reader: T, bookmark: &AssetBookmark) -> Result<AssetContent> {
-        let content = match bookmark.asset_type {
+        match bookmark.asset_type {
             AssetType::Text => Self::read_text(reader, bookmark),
             AssetType::Sound | AssetType::Music => Self::read_sound(reader, bookmark),
             AssetType::Texture => Self::read_texture(reader, bookmark),
             AssetType::Image => Self::read_image(reader, bookmark),
             _ => Ok(AssetContent::NotSupported),
-        };
-        content
+        }
     }
 
     /// Tries to convert a value to a AssetContent::Image variant
-    pub fn read_image<T: Read + Seek>(
-        mut reader: T,
-        bookmark: &AssetBookmark,
-    ) -> Result<AssetContent> {
+    pub fn read_image<T: Read + Seek>(reader: T, bookmark: &AssetBookmark) -> Result<AssetContent> {
+        let mut reader = OffsetReader::from_current(reader)?;
+
         // this is always bigger than the actual asset size
         // pulling data from this position will put us out of bounds
         let size = bookmark.size;
-        let mut data = vec![0; size as usize];
-        reader.read(&mut data)?;
+        let mut data = reader.read_bytes(size as usize)?;
 
         if let Some(pos) = find_last_sequence(&data, &JPEG_END) {
             let end = pos + 2; // add remaining bytes
@@ -62,124 +79,326 @@ impl AssetContent {
         Ok(Self::NotSupported)
     }
 
+    /// Reads the values found before the DDS file of a texture, leaving the DDS file untouched
+    pub fn read_texture_header<T: Read + Seek>(reader: T) -> Result<TextureHeader> {
+        let mut reader = OffsetReader::from_current(reader)?;
+        let (_, _, header) = Self::texture_header(&mut reader)?;
+        Ok(header)
+    }
+
+    /// Reads the values found before the DDS file, returning the width and height along with the header
+    fn texture_header<T: Read>(reader: &mut OffsetReader<T>) -> Result<(u32, u32, TextureHeader)> {
+        reader.expect(&[TEXTURE_HEADER_LENGTH])?;
+
+        let unknown1 = reader.read_u32()?;
+        let width = reader.read_u32()?;
+        let height = reader.read_u32()?;
+        let unknown2 = reader.read_u32()?;
+
+        reader.expect(&[TEXTURE_SETTINGS_MARKER])?;
+        let settings = reader.read_array::<TEXTURE_SETTINGS_LENGTH>()?;
+
+        let extra_length = reader.read_u8()?;
+        let extra = reader.read_bytes(extra_length as usize)?;
+
+        let header = TextureHeader {
+            unknown1,
+            unknown2,
+            settings,
+            extra,
+        };
+        Ok((width, height, header))
+    }
+
     /// Tries to convert a value to a AssetContent::Texture variant
     pub fn read_texture<T: Read + Seek>(
-        mut reader: T,
-        _bookmark: &AssetBookmark,
+        reader: T,
+        bookmark: &AssetBookmark,
     ) -> Result<AssetContent> {
-        let mut buffer = [0; 1];
-        reader.read(&mut buffer)?;
-        let [header_length] = buffer[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("texture header length"));
-        };
-        assert_eq!(header_length, 0x10);
-
-        let mut buffer = [0; 16];
-        reader.read(&mut buffer)?;
-        let [_unknown1, width, height, _unknown2] = unsafe {
-            let (_, values, _) = buffer.align_to::<u32>();
-            values.try_into().unwrap()
-        };
+        let mut reader = OffsetReader::from_current(reader)?;
+        let limit = reader.offset() + bookmark.size as u64;
+        let (width, height, header) = Self::texture_header(&mut reader)?;
+        let header = Box::new(header);
+
+        // DDS string starts here, pixel data is read up to the end of the content
+        let offset = reader.offset();
+        let length = limit.saturating_sub(offset);
+        let dds = Dds::read(reader.take(length)).map_err(|err| match err {
+            ddsfile::Error::ShortFile => AssetErrors::UnexpectedEof { offset },
+            ddsfile::Error::Io(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                AssetErrors::UnexpectedEof { offset }
+            }
+            err => err.into(),
+        })?;
+
+        Ok(AssetContent::Texture {
+            width,
+            height,
+            dds,
+            header,
+        })
+    }
 
-        // let pixels: u32 = height * length * 4;
+    /// Tries to parse content to a AssetContent::Sound variant
+    pub fn read_sound<T: Read + Seek>(reader: T, bookmark: &AssetBookmark) -> Result<AssetContent> {
+        let mut reader = OffsetReader::from_current(reader)?;
+        let mut properties = PropertyBag::read_bounded(
+            &mut reader,
+            bookmark.size as u64,
+            SOUND_SIZE_TOLERANCE as u64,
+            &SOUND_FLAGS,
+        )?;
+
+        match (
+            properties.remove(SOUND_ATTR_FILENAME),
+            properties.remove(SOUND_ATTR_FILEINBUFFER),
+        ) {
+            (Some(PropertyValue::String(filename)), Some(PropertyValue::Buffer(bytes))) => {
+                Ok(AssetContent::Sound {
+                    filename,
+                    size: bytes.len() as u32,
+                    bytes,
+                    properties,
+                })
+            }
+            _ => Ok(AssetContent::NotSupported),
+        }
+    }
 
-        let mut buffer = [0; 1];
-        reader.read(&mut buffer)?;
-        let [unknown] = buffer[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("unknown texture value"));
-        };
-        assert_eq!(unknown, 0x64);
+    /// Tries to parse content to a AssetContent::Text variant
+    pub fn read_text<T: Read + Seek>(reader: T, _bookmark: &AssetBookmark) -> Result<AssetContent> {
+        let mut reader = OffsetReader::from_current(reader)?;
+        let mut finished = false;
+        let mut contents: Vec<TextContent> = vec![];
 
-        // TODO: ???
-        let mut buffer = [0; 48];
-        reader.read(&mut buffer)?;
+        while !finished {
+            let mut text_length: u32 = 0;
+            let mut refs: Vec<String> = vec![];
 
-        let unknown_length = reader.read_u8()?;
+            loop {
+                let buffer = reader.read_array::<4>()?;
 
-        // TODO: ???
-        let mut buffer = vec![0; unknown_length as usize];
-        reader.read(&mut buffer)?;
+                // check if we've reach the end
+                if buffer == ASSET_NODE_END.as_bytes() {
+                    finished = true;
+                    break;
+                }
 
-        // DDS string starts here
-        let dds = Dds::read(reader)?;
+                // read as text length
+                let length = u32::from_le_bytes(buffer);
 
-        Ok(AssetContent::Texture { width, height, dds })
-    }
+                // read string
+                let _ref = reader.read_string(length as usize)?;
+                refs.push(_ref);
 
-    /// Tries to parse content to a AssetContent::Sound variant
-    pub fn read_sound<T: Read + Seek>(
-        mut reader: T,
-        bookmark: &AssetBookmark,
-    ) -> Result<AssetContent> {
-        let mut finished = false;
-        let fail_pos = reader.stream_position().unwrap() + bookmark.size as u64 + 16u64;
+                // read next byte
+                reader.expect(&[0x2])?;
 
-        let mut filename: Option<String> = None;
-        let mut size: Option<u32> = None;
-        let mut bytes: Option<Vec<u8>> = None;
+                // preview next len
+                let buffer = reader.read_array::<4>()?;
 
-        while !finished {
-            // fault tolerant check
-            if reader.stream_position().unwrap() >= fail_pos {
-                return Err(anyhow!(AssetErrors::ParserError).context("out of bounds"));
+                if buffer != [0, 0, 0, 0] {
+                    // check if this is the text length
+                    text_length = u32::from_le_bytes(buffer);
+                    break;
+                }
             }
 
-            let attr_name_length = reader.read_u32::<LittleEndian>()?;
+            if refs.is_empty() {
+                break;
+            }
 
-            // attr name
-            let mut buffer = vec![0; attr_name_length as usize];
-            reader.read(&mut buffer)?;
-            let (attr_name, _, _) = WINDOWS_1252.decode(&buffer);
-            let attr_name = attr_name.into_owned();
+            // read content
+            let content = reader.read_windows_1252(text_length as usize)?;
+            let asset = Self::text_content(refs, &content);
 
-            let attr_flag = reader.read_u8()?;
+            // TODO: rich text examples
+            // COLORS
+            // "{{CFFFFFF}}Necrostacy"
+            // "Activate {{#FFFF00}}Lucky Team{{#}}?"
+            // "Premium item obtained and {{#FFFF00}}activated{{#}} successfully!")
+            // "Tip: Press <{{CFFFFFF}}CTRL{{CBBBBBB}}> to begin combat mode"
+            // VARIABLES
+            // "You have received $count pieces of Ximerin!"
+            // REFS
+            // "This {1,2} is for me?"
+            // "The realm wants you to eliminate {1,1} {1,2}."
+            // CONDITIONS
+            // "Introduction|gender=1" male
+            // "Introduction|gender=0" female
+            // "Greeting|level=34-45"
 
-            if attr_name == SOUND_ATTR_FILENAME {
-                // attr_flag == 2
-                assert_eq!(attr_flag, 0x2);
+            contents.push(asset);
+        }
 
-                let attr_value_length = reader.read_u32::<LittleEndian>()?;
+        Ok(Self::Text { contents })
+    }
 
-                let mut buffer = vec![0; attr_value_length as usize];
-                reader.read(&mut buffer)?;
-                let (attr_value, _, _) = WINDOWS_1252.decode(&buffer);
-                let attr_value = attr_value.into_owned();
+    /// Splits a text into nodes, one for each line
+    ///
+    /// Empty lines are left out, only their line numbers are kept.
+    fn text_content(refs: Vec<String>, content: &str) -> TextContent {
+        let sep = String::from_iter(WINDOWS_SEPARATOR);
+        let mut nodes = vec![];
+        let mut empty_lines = vec![];
+
+        for (i, line) in content.split(&sep).enumerate() {
+            if line.is_empty() {
+                empty_lines.push(i);
+            } else {
+                nodes.push(parse_text(line));
+            }
+        }
 
-                filename = Some(attr_value)
-            } else if attr_name == SOUND_ATTR_FILEINBUFFER {
-                // attr_flag == 4
-                assert_eq!(attr_flag, 0x4);
+        TextContent {
+            refs,
+            nodes,
+            empty_lines,
+        }
+    }
 
-                let content_size = reader.read_u32::<LittleEndian>()?;
+    /// Parses content from a byte slice starting at the content of a record
+    pub fn parse<'a>(input: &'a [u8], bookmark: &AssetBookmark) -> SliceResult<'a, AssetContent> {
+        match bookmark.asset_type {
+            AssetType::Text => Self::parse_text(input, bookmark),
+            AssetType::Sound | AssetType::Music => Self::parse_sound(input, bookmark),
+            AssetType::Texture => Self::parse_texture(input, bookmark),
+            AssetType::Image => Self::parse_image(input, bookmark),
+            _ => Ok((input, AssetContent::NotSupported)),
+        }
+    }
 
-                let mut content = vec![0; content_size as usize];
-                reader.read(&mut content)?;
+    /// Parses a byte slice to a AssetContent::Image variant
+    pub fn parse_image<'a>(
+        input: &'a [u8],
+        bookmark: &AssetBookmark,
+    ) -> SliceResult<'a, AssetContent> {
+        let (input, data) = take(bookmark.size)(input)?;
+
+        match find_last_sequence(data, &JPEG_END) {
+            Some(pos) => Ok((
+                input,
+                AssetContent::Image {
+                    bytes: data[..pos + 2].to_vec(),
+                },
+            )),
+            None => Ok((input, AssetContent::NotSupported)),
+        }
+    }
 
-                size = Some(content_size);
-                bytes = Some(content);
+    /// Parses a byte slice to a AssetContent::Texture variant
+    pub fn parse_texture<'a>(
+        input: &'a [u8],
+        bookmark: &AssetBookmark,
+    ) -> SliceResult<'a, AssetContent> {
+        let start = input.len();
+        let (input, _) = magic(&[TEXTURE_HEADER_LENGTH])(input)?;
+        let (input, (unknown1, width, height, unknown2)) =
+            (le_u32, le_u32, le_u32, le_u32).parse(input)?;
+
+        let (input, _) = magic(&[TEXTURE_SETTINGS_MARKER])(input)?;
+        let (input, settings) = array::<TEXTURE_SETTINGS_LENGTH>(input)?;
+        let (input, extra_length) = le_u8(input)?;
+        let (input, extra) = take(extra_length)(input)?;
+
+        let header = Box::new(TextureHeader {
+            unknown1,
+            unknown2,
+            settings,
+            extra: extra.to_vec(),
+        });
+
+        // DDS string starts here, pixel data is read up to the end of the content
+        let length = (bookmark.size as usize)
+            .saturating_sub(start - input.len())
+            .min(input.len());
+        match Dds::read(&input[..length]) {
+            Ok(dds) => Ok((
+                &input[length..],
+                AssetContent::Texture {
+                    width,
+                    height,
+                    dds,
+                    header,
+                },
+            )),
+            Err(ddsfile::Error::ShortFile) => fail(input, SliceErrorKind::Eof),
+            Err(ddsfile::Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof => {
+                fail(input, SliceErrorKind::Eof)
             }
+            Err(err) => fail(input, SliceErrorKind::Asset(err.into())),
+        }
+    }
 
-            if filename.is_some() && bytes.is_some() {
-                finished = true;
-            }
+    /// Parses a byte slice to a AssetContent::Sound variant
+    pub fn parse_sound<'a>(
+        input: &'a [u8],
+        bookmark: &AssetBookmark,
+    ) -> SliceResult<'a, AssetContent> {
+        let (input, sound) = Self::parse_sound_attributes(input, bookmark)?;
+
+        match sound {
+            Some((filename, bytes, properties)) => Ok((
+                input,
+                AssetContent::Sound {
+                    filename,
+                    size: bytes.len() as u32,
+                    bytes: bytes.to_vec(),
+                    properties,
+                },
+            )),
+            None => Ok((input, AssetContent::NotSupported)),
         }
+    }
 
-        if filename.is_some() && bytes.is_some() {
-            return Ok(AssetContent::Sound {
-                filename: filename.unwrap(),
-                size: size.unwrap(),
-                bytes: bytes.unwrap(),
-            });
+    /// Parses sound attributes, returning the filename and the file when both are found
+    ///
+    /// The remaining attributes are returned as a list of properties.
+    pub(crate) fn parse_sound_attributes<'a>(
+        input: &'a [u8],
+        bookmark: &AssetBookmark,
+    ) -> SliceResult<'a, Option<(String, &'a [u8], PropertyBag)>> {
+        let (input, attributes) = PropertyBag::parse_properties(
+            input,
+            bookmark.size as usize,
+            SOUND_SIZE_TOLERANCE,
+            &SOUND_FLAGS,
+        )?;
+
+        let mut filename = None;
+        let mut bytes = None;
+        let mut properties = PropertyBag::new();
+        for (name, value) in attributes {
+            match value {
+                PropertyRef::String(value) if filename.is_none() && name == SOUND_ATTR_FILENAME => {
+                    filename = Some(value)
+                }
+                PropertyRef::Buffer(value)
+                    if bytes.is_none() && name == SOUND_ATTR_FILEINBUFFER =>
+                {
+                    bytes = Some(value)
+                }
+                value => properties.properties.push(Property {
+                    name,
+                    value: value.into(),
+                }),
+            }
         }
 
-        Ok(AssetContent::NotSupported)
+        Ok((
+            input,
+            filename
+                .zip(bytes)
+                .map(|(filename, bytes)| (filename, bytes, properties)),
+        ))
     }
 
-    /// Tries to parse content to a AssetContent::Text variant
-    pub fn read_text<T: Read + Seek>(
-        mut reader: T,
+    /// Parses a byte slice to a AssetContent::Text variant
+    pub fn parse_text<'a>(
+        input: &'a [u8],
         _bookmark: &AssetBookmark,
-    ) -> Result<AssetContent> {
+    ) -> SliceResult<'a, AssetContent> {
+        let mut input = input;
         let mut finished = false;
         let mut contents: Vec<TextContent> = vec![];
 
@@ -188,88 +407,196 @@ impl AssetContent {
             let mut refs: Vec<String> = vec![];
 
             loop {
-                let mut buffer = [0; 4];
-                reader.read(&mut buffer)?;
+                let (rest, buffer) = array::<4>(input)?;
+                input = rest;
 
                 // check if we've reach the end
-                let is_end = match String::from_utf8(buffer.into()) {
-                    Err(_) => false,
-                    Ok(value) => value == ASSET_NODE_END,
-                };
-                if is_end {
+                if buffer == ASSET_NODE_END.as_bytes() {
                     finished = true;
                     break;
                 }
 
                 // read as text length
-                let length = unsafe {
-                    let (_, values, _) = buffer.align_to::<u32>();
-                    values[0]
-                };
+                let length = u32::from_le_bytes(buffer);
 
                 // read string
-                let mut buffer = vec![0; length as usize];
-                reader.read(&mut buffer)?;
-                let _ref = String::from_utf8(buffer)?;
+                let (rest, _ref) = utf8_string(length as usize)(input)?;
                 refs.push(_ref);
 
                 // read next byte
-                let value = reader.read_u8()?;
-                assert_eq!(value, 0x2);
+                let (rest, _) = magic(&[0x2])(rest)?;
 
                 // preview next len
-                let mut buffer = [0; 4];
-                reader.read(&mut buffer)?;
+                let (rest, buffer) = array::<4>(rest)?;
+                input = rest;
 
                 if buffer != [0, 0, 0, 0] {
                     // check if this is the text length
-                    text_length = unsafe {
-                        let (_, values, _) = buffer.align_to::<u32>();
-                        values[0]
-                    };
+                    text_length = u32::from_le_bytes(buffer);
                     break;
                 }
             }
 
-            if refs.len() == 0 {
+            if refs.is_empty() {
                 break;
             }
 
             // read content
-            let mut buffer = vec![0; text_length as usize];
-            reader.read(&mut buffer)?;
-            let (content, _, _) = WINDOWS_1252.decode(&buffer);
+            let (rest, content) = windows_1252(text_length as usize)(input)?;
+            input = rest;
 
-            let sep = String::from_iter(WINDOWS_SEPARATOR);
-            let parts: Vec<&str> = content.split(&sep).collect();
+            contents.push(Self::text_content(refs, &content));
+        }
 
-            let nodes = parts
-                .iter()
-                .filter(|x| **x != "")
-                .map(|s| parse_text(s))
-                .collect();
+        Ok((input, Self::Text { contents }))
+    }
 
-            let asset = TextContent { refs, nodes };
+    /// Checks whether the content can be stored as an asset of the given type
+    pub fn is_compatible(&self, asset_type: &AssetType) -> bool {
+        matches!(
+            (self, asset_type),
+            (Self::Sound { .. }, AssetType::Sound | AssetType::Music)
+                | (Self::Texture { .. }, AssetType::Texture)
+                | (Self::Text { .. }, AssetType::Text)
+                | (Self::Image { .. }, AssetType::Image)
+        )
+    }
 
-            // TODO: rich text examples
-            // COLORS
-            // "{{CFFFFFF}}Necrostacy"
-            // "Activate {{#FFFF00}}Lucky Team{{#}}?"
-            // "Premium item obtained and {{#FFFF00}}activated{{#}} successfully!")
-            // "Tip: Press <{{CFFFFFF}}CTRL{{CBBBBBB}}> to begin combat mode"
-            // VARIABLES
-            // "You have received $count pieces of Ximerin!"
-            // REFS
-            // "This {1,2} is for me?"
-            // "The realm wants you to eliminate {1,1} {1,2}."
-            // CONDITIONS
-            // "Introduction|gender=1" male
-            // "Introduction|gender=0" female
-            // "Greeting|level=34-45"
+    /// Writes the content using the same layout expected by `read`
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        match self {
+            Self::Sound {
+                filename,
+                bytes,
+                properties,
+                ..
+            } => Self::write_sound(writer, filename, bytes, properties),
+            Self::Text { contents } => Self::write_text(writer, contents),
+            Self::Image { bytes } => {
+                writer.write_all(bytes)?;
+                Ok(())
+            }
+            Self::Texture {
+                width,
+                height,
+                dds,
+                header,
+            } => Self::write_texture(writer, *width, *height, dds, header),
+            Self::NotSupported => Err(AssetErrors::UnsupportedContentError("not supported".into())),
+        }
+    }
 
-            contents.push(asset);
+    /// Returns the content as it would be stored on a database file
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Lists the mip levels, array layers and cubemap faces of a texture
+    ///
+    /// Contents other than textures have no surfaces.
+    pub fn surfaces(&self) -> Result<Vec<TextureSurface>> {
+        match self {
+            Self::Texture {
+                width, height, dds, ..
+            } => TextureSurface::list(dds, *width, *height),
+            _ => Ok(vec![]),
         }
+    }
 
-        Ok(Self::Text { contents })
+    /// Writes a texture header followed by the DDS file
+    fn write_texture<T: Write>(
+        mut writer: T,
+        width: u32,
+        height: u32,
+        dds: &Dds,
+        header: &TextureHeader,
+    ) -> Result<()> {
+        // checked first, so nothing is written for an invalid header
+        let extra_length = u8::try_from(header.extra.len())?;
+
+        writer.write_u8(TEXTURE_HEADER_LENGTH)?;
+        writer.write_u32::<LittleEndian>(header.unknown1)?;
+        writer.write_u32::<LittleEndian>(width)?;
+        writer.write_u32::<LittleEndian>(height)?;
+        writer.write_u32::<LittleEndian>(header.unknown2)?;
+        writer.write_u8(TEXTURE_SETTINGS_MARKER)?;
+        writer.write_all(&header.settings)?;
+        writer.write_u8(extra_length)?;
+        writer.write_all(&header.extra)?;
+
+        dds.write(&mut writer)?;
+        Ok(())
+    }
+
+    /// Writes a sound as a list of attributes, starting with the filename and the file
+    fn write_sound<T: Write>(
+        mut writer: T,
+        filename: &str,
+        bytes: &[u8],
+        properties: &PropertyBag,
+    ) -> Result<()> {
+        let mut attributes = PropertyBag::new();
+        attributes.set(SOUND_ATTR_FILENAME, PropertyValue::String(filename.into()));
+        attributes.set(
+            SOUND_ATTR_FILEINBUFFER,
+            PropertyValue::Buffer(bytes.to_vec()),
+        );
+        attributes.write(&mut writer)?;
+        properties.write(writer)
+    }
+
+    /// Writes a list of text components
+    ///
+    /// Nodes are joined using a Windows separator, adding back the empty lines
+    /// at their line numbers. Every component needs at least one ref and some
+    /// text, as a zero length is read as another ref.
+    fn write_text<T: Write>(mut writer: T, contents: &[TextContent]) -> Result<()> {
+        let sep = String::from_iter(WINDOWS_SEPARATOR);
+
+        let texts = contents
+            .iter()
+            .map(|content| {
+                let mut lines = content
+                    .nodes
+                    .iter()
+                    .map(|node| node.to_string())
+                    .collect::<Vec<String>>();
+                for &line in &content.empty_lines {
+                    lines.insert(line.min(lines.len()), String::new());
+                }
+                lines.join(&sep)
+            })
+            .collect::<Vec<String>>();
+
+        for (content, text) in contents.iter().zip(&texts) {
+            if content.refs.is_empty() || text.is_empty() {
+                return Err(AssetErrors::UnsupportedContentError(
+                    "text contents need at least one ref and some text".into(),
+                ));
+            }
+        }
+
+        for (content, text) in contents.iter().zip(&texts) {
+            let (text, _, _) = WINDOWS_1252.encode(text);
+
+            for (i, _ref) in content.refs.iter().enumerate() {
+                writer.write_u32::<LittleEndian>(u32::try_from(_ref.len())?)?;
+                writer.write_all(_ref.as_bytes())?;
+                writer.write_u8(0x2)?;
+
+                // the last ref is followed by the text length
+                if i + 1 < content.refs.len() {
+                    writer.write_u32::<LittleEndian>(0)?;
+                } else {
+                    writer.write_u32::<LittleEndian>(u32::try_from(text.len())?)?;
+                }
+            }
+
+            writer.write_all(&text)?;
+        }
+
+        Ok(())
     }
 }
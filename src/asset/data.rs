@@ -1,10 +1,16 @@
 use super::bookmark::AssetBookmark;
-use super::{AssetType, ASSET_NODE_START};
+use super::{AssetType, ASSET_NODE_END, ASSET_NODE_START, ASSET_TYPE_PCAUTH};
+use crate::database::NODE_HEADER_LENGTH;
+use crate::errors::{AssetErrors, ParseDiagnostic, ParseOptions, Result};
+use crate::parser::{array, into_asset_error, magic, utf8_string, windows_1252, SliceResult};
+use crate::reader::OffsetReader;
 use crate::AssetContent;
-use anyhow::Result;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, WriteBytesExt};
 use encoding_rs::WINDOWS_1252;
-use std::io::{Read, Seek, SeekFrom};
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u32, le_u8};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
 
 /// A wrapper struct containing the data retrieved from the asset database file
 pub struct AssetData {
@@ -20,116 +26,967 @@ pub struct AssetData {
     pub resource_id: u32,
     /// The actual content
     pub content: AssetContent,
+    /// Problems skipped while parsing in lenient mode
+    pub diagnostics: Vec<ParseDiagnostic>,
 
     // TODO
     _unknown: u32,
     _unknown2: [u8; 16],
     _unknown3: [u8; 16],
     _unknown4: u32,
+    _unknown5: [u8; 16],
     _maybe_size: u32,
+    _asset_type_name: Vec<u8>,
+}
+
+/// The values stored in a database record, read without its content
+#[derive(Debug, Clone)]
+pub struct AssetHeader {
+    /// The asset type
+    pub asset_type: AssetType,
+    /// A string with the form 'resource_...'
+    pub uid: String,
+    /// A string with the form 'TYPE::NAME'
+    pub resource_name: String,
+    /// The asset name
+    pub asset_name: String,
+    /// An unique identifier
+    pub resource_id: u32,
+    /// The location of the content within the database file
+    pub payload: Range<u64>,
+    /// The location of the record within the database file, from its `PAIR` string to the end of its `RIAP` string
+    pub record: Range<u64>,
+}
+
+impl AssetHeader {
+    /// Builds a bookmark pointing to the record, so its content can be read
+    ///
+    /// The block header is assumed to precede the record, as done for
+    /// every record listed in an index.
+    pub fn bookmark(&self) -> AssetBookmark {
+        let node_end = self.record.start as usize;
+        let node_start = node_end.saturating_sub(NODE_HEADER_LENGTH);
+
+        AssetBookmark {
+            uid: self.uid.clone(),
+            resource_id: Some(self.resource_id),
+            asset_type: self.asset_type.clone(),
+            name: Some(self.asset_name.clone()),
+            resource_name: self.resource_name.clone(),
+            node_start,
+            node_end,
+            node_next: self.record.end as usize,
+            node_previous: 0,
+            node_type: 0,
+            item_start: node_start,
+            item_unknown: 0,
+            size: (self.payload.end - self.payload.start) as u32,
+        }
+    }
 }
 
 impl AssetData {
-    pub fn read<T: Read + Seek>(mut reader: T, bookmark: &AssetBookmark) -> Result<Self> {
+    /// Creates a new asset, leaving all unidentified header values zeroed
+    ///
+    /// Game files hold other values there, so assets being repacked should
+    /// be the ones read from the original database instead.
+    pub fn new(
+        asset_type: AssetType,
+        resource_id: u32,
+        asset_name: &str,
+        content: AssetContent,
+    ) -> Self {
+        let type_name: &str = asset_type.clone().into();
+
+        // the auth asset is identified by its uid
+        let uid = match asset_type {
+            AssetType::Auth => ASSET_TYPE_PCAUTH.to_string(),
+            _ => format!("resource_{}", resource_id),
+        };
+
+        AssetData {
+            asset_type,
+            uid,
+            resource_name: format!("{}::{}", type_name, asset_name),
+            asset_name: asset_name.into(),
+            resource_id,
+            content,
+            diagnostics: vec![],
+            _unknown: 0,
+            _unknown2: [0; 16],
+            _unknown3: [0; 16],
+            _unknown4: 0,
+            _unknown5: [0; 16],
+            // the size written to the record is the one of the payload
+            _maybe_size: 0,
+            _asset_type_name: type_name.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn read<T: Read + Seek>(reader: T, bookmark: &AssetBookmark) -> Result<Self> {
+        Self::read_with_options(reader, bookmark, ParseOptions::default())
+    }
+
+    /// Reads an asset, recovering from invalid sizes and contents unless parsing in strict mode
+    ///
+    /// In lenient mode, the size stored in the record is preferred over the
+    /// one found in the bookmark, and content that cannot be parsed is
+    /// returned as `AssetContent::NotSupported`.
+    pub fn read_with_options<T: Read + Seek>(
+        mut reader: T,
+        bookmark: &AssetBookmark,
+        options: ParseOptions,
+    ) -> Result<Self> {
         let pos = bookmark.node_end;
         reader.seek(SeekFrom::Start(pos as u64))?;
+        let mut reader = OffsetReader::with_offset(reader, pos as u64);
+
+        let header = RecordHeader::read(&mut reader)?;
+        let offset = reader.offset();
+
+        Self::from_header(header, bookmark, options, offset, |bookmark| {
+            AssetContent::read(reader, bookmark)
+        })
+    }
+
+    /// Parses an asset from the contents of a database file
+    pub fn parse(data: &[u8], bookmark: &AssetBookmark) -> Result<Self> {
+        Self::parse_with_options(data, bookmark, ParseOptions::default())
+    }
+
+    /// Parses an asset from the contents of a database file, recovering from
+    /// invalid sizes and contents unless parsing in strict mode
+    pub fn parse_with_options(
+        data: &[u8],
+        bookmark: &AssetBookmark,
+        options: ParseOptions,
+    ) -> Result<Self> {
+        let Some(input) = data.get(bookmark.node_end..) else {
+            return Err(AssetErrors::UnexpectedEof {
+                offset: data.len() as u64,
+            });
+        };
+
+        let (input, header) =
+            RecordHeader::parse(input).map_err(|err| into_asset_error(err, data))?;
+        let offset = (data.len() - input.len()) as u64;
+
+        Self::from_header(header, bookmark, options, offset, |bookmark| {
+            AssetContent::parse(input, bookmark)
+                .map(|(_, content)| content)
+                .map_err(|err| into_asset_error(err, data))
+        })
+    }
+
+    /// Reads the header of an asset, leaving its content untouched
+    pub fn read_header<T: Read + Seek>(
+        mut reader: T,
+        bookmark: &AssetBookmark,
+    ) -> Result<AssetHeader> {
+        let pos = bookmark.node_end;
+        reader.seek(SeekFrom::Start(pos as u64))?;
+        let mut reader = OffsetReader::with_offset(reader, pos as u64);
+
+        let header = RecordHeader::read(&mut reader)?;
+        Self::asset_header(header, bookmark, reader.offset())
+    }
+
+    /// Reads an asset record without parsing its content, left as `AssetContent::NotSupported`
+    ///
+    /// Unlike `read_header`, the unidentified values of the record are kept,
+    /// so the record can be written back with another content.
+    pub(crate) fn read_record<T: Read + Seek>(
+        mut reader: T,
+        bookmark: &AssetBookmark,
+    ) -> Result<Self> {
+        let pos = bookmark.node_end;
+        reader.seek(SeekFrom::Start(pos as u64))?;
+        let mut reader = OffsetReader::with_offset(reader, pos as u64);
+
+        let header = RecordHeader::read(&mut reader)?;
+        let offset = reader.offset();
+        Self::from_header(header, bookmark, ParseOptions::default(), offset, |_| {
+            Ok(AssetContent::NotSupported)
+        })
+    }
+
+    /// Parses the header of an asset from the contents of a database file
+    pub fn parse_header(data: &[u8], bookmark: &AssetBookmark) -> Result<AssetHeader> {
+        let Some(input) = data.get(bookmark.node_end..) else {
+            return Err(AssetErrors::UnexpectedEof {
+                offset: data.len() as u64,
+            });
+        };
+
+        let (input, header) =
+            RecordHeader::parse(input).map_err(|err| into_asset_error(err, data))?;
+        Self::asset_header(header, bookmark, (data.len() - input.len()) as u64)
+    }
+
+    /// Reads the content of any asset as stored in the database file, without parsing it
+    pub fn read_raw<T: Read + Seek>(mut reader: T, bookmark: &AssetBookmark) -> Result<Vec<u8>> {
+        let header = Self::read_header(&mut reader, bookmark)?;
+
+        let start = header.payload.start;
+        reader.seek(SeekFrom::Start(start))?;
+        let mut reader = OffsetReader::with_offset(reader, start);
+        reader.read_bytes(bookmark.size as usize)
+    }
 
+    /// Returns the content of any asset as stored in the contents of a database file
+    pub fn parse_raw<'a>(data: &'a [u8], bookmark: &AssetBookmark) -> Result<&'a [u8]> {
+        let header = Self::parse_header(data, bookmark)?;
+
+        let start = header.payload.start as usize;
+        data.get(start..start + bookmark.size as usize)
+            .ok_or(AssetErrors::UnexpectedEof {
+                offset: start as u64,
+            })
+    }
+
+    /// Reads the block header found before the record of an asset
+    ///
+    /// Its meaning is not known yet, it can be given to
+    /// `DatabaseWriter::add_with_block_header` to keep it when repacking.
+    pub fn read_block_header<T: Read + Seek>(
+        mut reader: T,
+        bookmark: &AssetBookmark,
+    ) -> Result<[u8; NODE_HEADER_LENGTH]> {
+        let pos = bookmark.node_start as u64;
+        reader.seek(SeekFrom::Start(pos))?;
+        OffsetReader::with_offset(reader, pos).read_array::<NODE_HEADER_LENGTH>()
+    }
+
+    fn asset_header(
+        header: RecordHeader,
+        bookmark: &AssetBookmark,
+        content_offset: u64,
+    ) -> Result<AssetHeader> {
+        if header.size != bookmark.size {
+            return Err(AssetErrors::SizeMismatch {
+                expected: bookmark.size as u64,
+                found: header.size as u64,
+                offset: bookmark.node_end as u64 + header.size_offset,
+            });
+        }
+
+        Ok(AssetHeader {
+            asset_type: bookmark.asset_type.clone(),
+            uid: header.uid,
+            resource_name: header.resource_name,
+            asset_name: header.asset_name,
+            resource_id: header.resource_id,
+            payload: content_offset..content_offset + header.size as u64,
+            record: bookmark.node_end as u64
+                ..content_offset + header.size as u64 + ASSET_NODE_END.len() as u64,
+        })
+    }
+
+    /// Builds an asset from its record header, reading its content with the given function
+    fn from_header<F>(
+        header: RecordHeader,
+        bookmark: &AssetBookmark,
+        options: ParseOptions,
+        content_offset: u64,
+        read_content: F,
+    ) -> Result<Self>
+    where
+        F: FnOnce(&AssetBookmark) -> Result<AssetContent>,
+    {
+        let mut diagnostics = vec![];
+
+        let mut bookmark = bookmark.clone();
+        if header.size != bookmark.size {
+            let offset = bookmark.node_end as u64 + header.size_offset;
+            let err = AssetErrors::SizeMismatch {
+                expected: bookmark.size as u64,
+                found: header.size as u64,
+                offset,
+            };
+            diagnostics.push(ParseDiagnostic::check(&options, offset, err)?);
+            bookmark.size = header.size;
+        }
+
+        let content = match read_content(&bookmark) {
+            Ok(content) => content,
+            Err(err @ AssetErrors::IoError(_)) => return Err(err),
+            Err(err) => {
+                diagnostics.push(ParseDiagnostic::check(&options, content_offset, err)?);
+                AssetContent::NotSupported
+            }
+        };
+
+        Ok(AssetData {
+            asset_type: bookmark.asset_type.clone(),
+            uid: header.uid,
+            resource_id: header.resource_id,
+            resource_name: header.resource_name,
+            asset_name: header.asset_name,
+            content,
+            diagnostics,
+            _unknown: header.unknown,
+            _unknown2: header.unknown2,
+            _unknown3: header.unknown3,
+            _unknown4: header.unknown4,
+            _unknown5: header.unknown5,
+            _maybe_size: header.size,
+            _asset_type_name: header.asset_type_name,
+        })
+    }
+
+    /// Returns the number of bytes between the start of the record and its content
+    pub fn header_length(&self) -> usize {
+        let (resource_name, _, _) = WINDOWS_1252.encode(&self.resource_name);
+        let (asset_name, _, _) = WINDOWS_1252.encode(&self.asset_name);
+
+        ASSET_NODE_START.len()
+            + 4
+            + 1
+            + self._unknown2.len()
+            + self.uid.len()
+            + 1
+            + resource_name.len()
+            + 4
+            + 4
+            + self._unknown3.len()
+            + 4
+            + 4
+            + 4
+            + 4
+            + self._asset_type_name.len()
+            + 4
+            + asset_name.len()
+            + self._unknown5.len()
+    }
+
+    /// Writes the asset as a database record holding the given payload
+    ///
+    /// The payload is expected to be the encoded content, as returned by
+    /// `AssetContent::to_bytes`.
+    pub fn write<T: Write>(&self, mut writer: T, payload: &[u8]) -> Result<()> {
         // PAIR string
-        let mut buffer = vec![0; 4];
-        reader.read(&mut buffer)?;
-        let node = String::from_utf8(buffer).unwrap();
-        assert_eq!(node, ASSET_NODE_START);
+        writer.write_all(ASSET_NODE_START.as_bytes())?;
+        writer.write_u32::<LittleEndian>(self._unknown)?;
+
+        // uid
+        writer.write_u8(u8::try_from(self.uid.len())?)?;
+        writer.write_all(&self._unknown2)?;
+        writer.write_all(self.uid.as_bytes())?;
+
+        // resource name
+        let (resource_name, _, _) = WINDOWS_1252.encode(&self.resource_name);
+        writer.write_u8(u8::try_from(resource_name.len())?)?;
+        writer.write_all(&resource_name)?;
+
+        // separator + size
+        writer.write_all(&[0, 0, 0, 0])?;
+        writer.write_u32::<LittleEndian>(u32::try_from(payload.len())?)?;
+        writer.write_all(&self._unknown3)?;
+        writer.write_all(&[1, 0, 0, 0])?;
+
+        // resource id
+        writer.write_u32::<LittleEndian>(self.resource_id)?;
+        writer.write_u32::<LittleEndian>(self._unknown4)?;
+
+        // asset type
+        writer.write_u32::<LittleEndian>(u32::try_from(self._asset_type_name.len())?)?;
+        writer.write_all(&self._asset_type_name)?;
+
+        // asset name
+        let (asset_name, _, _) = WINDOWS_1252.encode(&self.asset_name);
+        writer.write_u32::<LittleEndian>(u32::try_from(asset_name.len())?)?;
+        writer.write_all(&asset_name)?;
+
+        writer.write_all(&self._unknown5)?;
+
+        // content
+        writer.write_all(payload)?;
+
+        // RIAP string
+        writer.write_all(ASSET_NODE_END.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// The values stored in a database record before its content
+pub(crate) struct RecordHeader {
+    pub uid: String,
+    pub resource_name: String,
+    pub asset_name: String,
+    pub resource_id: u32,
+    /// The content size
+    pub size: u32,
+    /// Offset of the size value from the start of the record, used to report mismatches
+    pub size_offset: u64,
+    pub asset_type_name: Vec<u8>,
+
+    // TODO
+    pub unknown: u32,
+    pub unknown2: [u8; 16],
+    pub unknown3: [u8; 16],
+    pub unknown4: u32,
+    pub unknown5: [u8; 16],
+}
+
+impl RecordHeader {
+    /// Reads a record header, starting with the PAIR string
+    pub fn read<T: Read>(reader: &mut OffsetReader<T>) -> Result<Self> {
+        let start = reader.offset();
+
+        // PAIR string
+        reader.expect(ASSET_NODE_START.as_bytes())?;
 
         // TODO: ???
-        let unknown = reader.read_u32::<LittleEndian>()?;
+        let unknown = reader.read_u32()?;
 
         // uid length
         let uid_length = reader.read_u8()?;
 
         // TODO: ???
-        let mut unknown2 = [0; 16];
-        reader.read(&mut unknown2)?;
+        let unknown2 = reader.read_array::<16>()?;
 
         // uid
-        let mut buffer = vec![0; uid_length as usize];
-        reader.read(&mut buffer)?;
-        let uid = String::from_utf8(buffer)?;
+        let uid = reader.read_string(uid_length.into())?;
 
         // resource name length
         let resource_name_length = reader.read_u8()?;
 
         // resource name
-        let mut buffer = vec![0; resource_name_length as usize];
-        reader.read(&mut buffer)?;
-        let (resource_name, _, _) = WINDOWS_1252.decode(&buffer);
-        let resource_name = resource_name.into_owned();
+        let resource_name = reader.read_windows_1252(resource_name_length.into())?;
 
         // separator
-        let mut buffer = [0; 4];
-        reader.read(&mut buffer)?;
-        assert_eq!(buffer, [0, 0, 0, 0]);
+        reader.expect(&[0, 0, 0, 0])?;
 
         // TODO: size?
-        let maybe_size = reader.read_u32::<LittleEndian>()?;
-        assert_eq!(maybe_size, bookmark.size);
+        let size_offset = reader.offset() - start;
+        let size = reader.read_u32()?;
 
         // TODO: ???
-        let mut unknown3 = [0; 16];
-        reader.read(&mut unknown3)?;
+        let unknown3 = reader.read_array::<16>()?;
 
         // TODO: ???
-        let mut buffer = [0; 4];
-        reader.read(&mut buffer)?;
-        assert_eq!(buffer, [1, 0, 0, 0]);
+        reader.expect(&[1, 0, 0, 0])?;
 
         // resource id
-        let resource_id = reader.read_u32::<LittleEndian>()?;
+        let resource_id = reader.read_u32()?;
 
         // TODO: ???
-        let unknown4 = reader.read_u32::<LittleEndian>()?;
+        let unknown4 = reader.read_u32()?;
 
         // asset type length
-        let asset_type_length = reader.read_u32::<LittleEndian>()?;
+        let asset_type_length = reader.read_u32()?;
 
         // asset type
-        let mut buffer = vec![0; asset_type_length as usize];
-        reader.read(&mut buffer)?;
+        let asset_type_name = reader.read_bytes(asset_type_length as usize)?;
 
         // asset name length
-        let asset_name_length = reader.read_u32::<LittleEndian>()?;
+        let asset_name_length = reader.read_u32()?;
 
         // asset name
-        let mut buffer = vec![0; asset_name_length as usize];
-        reader.read(&mut buffer)?;
-        let (asset_name, _, _) = WINDOWS_1252.decode(&buffer);
-        let asset_name = asset_name.into_owned();
+        let asset_name = reader.read_windows_1252(asset_name_length as usize)?;
 
         // TODO: ???
-        let mut buffer = [0; 16];
-        reader.read(&mut buffer)?;
-
-        let content = AssetContent::read(reader, bookmark)?;
+        let unknown5 = reader.read_array::<16>()?;
 
-        Ok(AssetData {
-            asset_type: bookmark.asset_type.clone(),
+        Ok(Self {
             uid,
-            resource_id,
             resource_name,
             asset_name,
-            content,
-            _unknown: unknown,
-            _unknown2: unknown2,
-            _unknown3: unknown3,
-            _unknown4: unknown4,
-            _maybe_size: maybe_size,
+            resource_id,
+            size,
+            size_offset,
+            asset_type_name,
+            unknown,
+            unknown2,
+            unknown3,
+            unknown4,
+            unknown5,
         })
     }
+
+    /// Parses a record header from a byte slice, starting with the PAIR string
+    pub fn parse(input: &[u8]) -> SliceResult<'_, Self> {
+        let start = input.len();
+
+        // PAIR string
+        let (input, _) = magic(ASSET_NODE_START.as_bytes())(input)?;
+
+        // TODO: ???
+        let (input, unknown) = le_u32(input)?;
+
+        // uid
+        let (input, uid_length) = le_u8(input)?;
+        let (input, unknown2) = array::<16>(input)?;
+        let (input, uid) = utf8_string(uid_length.into())(input)?;
+
+        // resource name
+        let (input, resource_name_length) = le_u8(input)?;
+        let (input, resource_name) = windows_1252(resource_name_length.into())(input)?;
+
+        // separator
+        let (input, _) = magic(&[0, 0, 0, 0])(input)?;
+
+        // TODO: size?
+        let size_offset = (start - input.len()) as u64;
+        let (input, size) = le_u32(input)?;
+
+        // TODO: ???
+        let (input, unknown3) = array::<16>(input)?;
+        let (input, _) = magic(&[1, 0, 0, 0])(input)?;
+
+        // resource id
+        let (input, resource_id) = le_u32(input)?;
+
+        // TODO: ???
+        let (input, unknown4) = le_u32(input)?;
+
+        // asset type
+        let (input, asset_type_length) = le_u32(input)?;
+        let (input, asset_type_name) = take(asset_type_length)(input)?;
+
+        // asset name
+        let (input, asset_name_length) = le_u32(input)?;
+        let (input, asset_name) = windows_1252(asset_name_length as usize)(input)?;
+
+        // TODO: ???
+        let (input, unknown5) = array::<16>(input)?;
+
+        Ok((
+            input,
+            Self {
+                uid,
+                resource_name,
+                asset_name,
+                resource_id,
+                size,
+                size_offset,
+                asset_type_name: asset_type_name.to_vec(),
+                unknown,
+                unknown2,
+                unknown3,
+                unknown4,
+                unknown5,
+            },
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{AssetContent, AssetData, AssetType, ResourceIndex};
+    use crate::asset::{PropertyBag, PropertyValue};
+    use crate::asset::{TextContent, TextNode, ASSET_NODE_END};
+    use crate::database::tests::sound;
+    use crate::errors::{AssetErrors, ParseOptions};
+    use crate::{AssetContent, AssetData, AssetType, DatabaseWriter, ResourceIndex};
+    use ddsfile::{D3DFormat, Dds, NewD3dParams};
     use std::fs::File;
+    use std::io::Cursor;
+
+    fn image_database() -> (Vec<u8>, ResourceIndex) {
+        let asset = AssetData::new(
+            AssetType::Image,
+            75879,
+            "loading",
+            AssetContent::Image {
+                bytes: vec![0xFF, 0xD8, 0xFF, 0xD9],
+            },
+        );
+
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add(&asset).unwrap();
+        let index = database.finish().unwrap();
+        (sdb, index)
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let (mut sdb, index) = image_database();
+        let image = index.get_by_resource_id(75879).unwrap();
+        sdb[image.node_end] = b'X';
+
+        let err = AssetData::read(Cursor::new(&sdb), &image).err().unwrap();
+        match &err {
+            AssetErrors::BadMagic { found, offset, .. } => {
+                assert_eq!(found, b"XAIR");
+                assert_eq!(*offset, image.node_end as u64);
+            }
+            _ => panic!("expected a bad magic error"),
+        }
+
+        // the corrupted bytes belong to the image
+        let owner = index.asset_at_offset(err.offset().unwrap()).unwrap();
+        assert_eq!(owner.resource_id, Some(75879));
+    }
+
+    #[test]
+    fn test_read_header() {
+        let (sdb, index) = image_database();
+        let image = index.get_by_resource_id(75879).unwrap();
+
+        let header = AssetData::read_header(Cursor::new(&sdb), &image).unwrap();
+        assert_eq!(header.uid, "resource_75879");
+        assert_eq!(header.resource_name, "IMAGE::loading");
+        assert_eq!(header.asset_name, "loading");
+        assert_eq!(header.resource_id, 75879);
+
+        let payload = header.payload.start as usize..header.payload.end as usize;
+        assert_eq!(sdb[payload], [0xFF, 0xD8, 0xFF, 0xD9]);
+        assert_eq!(
+            &sdb[header.payload.end as usize..],
+            ASSET_NODE_END.as_bytes()
+        );
+
+        // slices give the same range
+        let parsed = AssetData::parse_header(&sdb, &image).unwrap();
+        assert_eq!(parsed.payload, header.payload);
+    }
+
+    #[test]
+    fn test_sound_properties() {
+        let mut properties = PropertyBag::new();
+        properties.set("volume", PropertyValue::String("0.8".into()));
+        properties.set(
+            "looping",
+            PropertyValue::Unknown {
+                flag: 1,
+                bytes: vec![1, 0, 0, 0],
+            },
+        );
+
+        let asset = AssetData::new(
+            AssetType::Sound,
+            50677,
+            "combat_pain_male_3",
+            AssetContent::Sound {
+                filename: "combat_pain_male_3.ogg".into(),
+                size: 4,
+                bytes: b"OggS".to_vec(),
+                properties: properties.clone(),
+            },
+        );
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add(&asset).unwrap();
+        let index = database.finish().unwrap();
+        let sound = index.get_by_resource_id(50677).unwrap();
+
+        // attributes other than the file are kept
+        let read = AssetData::read(Cursor::new(&sdb), &sound).unwrap();
+        let parsed = AssetData::parse(&sdb, &sound).unwrap();
+        for content in [read.content, parsed.content] {
+            match content {
+                AssetContent::Sound {
+                    filename,
+                    bytes,
+                    properties: found,
+                    ..
+                } => {
+                    assert_eq!(filename, "combat_pain_male_3.ogg");
+                    assert_eq!(bytes, b"OggS");
+                    assert_eq!(found, properties);
+                }
+                _ => panic!("expected a sound"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sound_attributes() {
+        let mut payload = vec![];
+        let mut attributes = PropertyBag::new();
+        attributes.set("filename", PropertyValue::Buffer(b"a.ogg".to_vec()));
+        attributes.set("file_in_buffer", PropertyValue::Buffer(b"OggS".to_vec()));
+        attributes.write(&mut payload).unwrap();
+
+        let asset = AssetData::new(AssetType::Sound, 1, "sound", AssetContent::NotSupported);
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add_payload(&asset, &payload).unwrap();
+        let index = database.finish().unwrap();
+        let bookmark = index.get_by_resource_id(1).unwrap();
+        let start = AssetData::read_header(Cursor::new(&sdb), &bookmark)
+            .unwrap()
+            .payload
+            .start;
+
+        // the filename must be stored as a string
+        let flag_offset = start + 4 + "filename".len() as u64;
+        let read = AssetData::read(Cursor::new(&sdb), &bookmark).err();
+        let parsed = AssetData::parse(&sdb, &bookmark).err();
+        for err in [read, parsed] {
+            match err {
+                Some(AssetErrors::BadMagic {
+                    expected,
+                    found,
+                    offset,
+                }) => {
+                    assert_eq!((expected, found), (vec![2], vec![4]));
+                    assert_eq!(offset, flag_offset);
+                }
+                _ => panic!("expected a bad magic error"),
+            }
+        }
+
+        // attributes may go a few bytes past the content size
+        let asset = sound(50677, &[1; 64]);
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add(&asset).unwrap();
+        let index = database.finish().unwrap();
+        let mut bookmark = index.get_by_resource_id(50677).unwrap();
+        let start = AssetData::read_header(Cursor::new(&sdb), &bookmark)
+            .unwrap()
+            .payload
+            .start;
+
+        bookmark.size -= 16;
+        let mut reader = Cursor::new(&sdb);
+        reader.set_position(start);
+        let content = AssetContent::read_sound(&mut reader, &bookmark).unwrap();
+        assert!(matches!(content, AssetContent::Sound { .. }));
+        let (_, content) = AssetContent::parse_sound(&sdb[start as usize..], &bookmark).unwrap();
+        assert!(matches!(content, AssetContent::Sound { .. }));
+
+        bookmark.size -= 1;
+        reader.set_position(start);
+        assert!(matches!(
+            AssetContent::read_sound(&mut reader, &bookmark),
+            Err(AssetErrors::OutOfBounds { .. })
+        ));
+        assert!(AssetContent::parse_sound(&sdb[start as usize..], &bookmark).is_err());
+    }
+
+    #[test]
+    fn test_read_raw() {
+        let asset = AssetData::new(AssetType::Mesh, 1234, "sword", AssetContent::NotSupported);
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add_payload(&asset, b"mesh data").unwrap();
+        let index = database.finish().unwrap();
+        let mesh = index.get_by_resource_id(1234).unwrap();
+
+        // the content is not parsed, but can be read as is
+        let asset = AssetData::read(Cursor::new(&sdb), &mesh).unwrap();
+        assert!(matches!(asset.content, AssetContent::NotSupported));
+        let bytes = AssetData::read_raw(Cursor::new(&sdb), &mesh).unwrap();
+        assert_eq!(bytes, b"mesh data");
+        assert_eq!(AssetData::parse_raw(&sdb, &mesh).unwrap(), b"mesh data");
+
+        match AssetData::parse_raw(&sdb[..sdb.len() - 8], &mesh) {
+            Err(AssetErrors::UnexpectedEof { offset }) => {
+                assert_eq!(offset as usize, sdb.len() - 13)
+            }
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_texture_roundtrip() {
+        let dds = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let mut payload = vec![0x10];
+        [7u32, 4, 4, 9]
+            .iter()
+            .for_each(|v| payload.extend_from_slice(&v.to_le_bytes()));
+        payload.push(0x64);
+        payload.extend((0..48).map(|i| i as u8));
+        payload.extend_from_slice(&[3, 0xA, 0xB, 0xC]);
+        dds.write(&mut payload).unwrap();
+
+        let asset = AssetData::new(
+            AssetType::Texture,
+            1260,
+            "grass",
+            AssetContent::NotSupported,
+        );
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add_payload(&asset, &payload).unwrap();
+        let index = database.finish().unwrap();
+        let bookmark = &index.bookmarks()[0];
+
+        let read = AssetData::read(Cursor::new(&sdb), bookmark).unwrap();
+        let parsed = AssetData::parse(&sdb, bookmark).unwrap();
+        for asset in [&read, &parsed] {
+            match &asset.content {
+                AssetContent::Texture {
+                    width,
+                    height,
+                    header,
+                    ..
+                } => {
+                    assert_eq!((*width, *height), (4, 4));
+                    assert_eq!((header.unknown1, header.unknown2), (7, 9));
+                    assert_eq!(header.settings[47], 47);
+                    assert_eq!(header.extra, vec![0xA, 0xB, 0xC]);
+                }
+                _ => panic!("expected a texture"),
+            }
+        }
+
+        // the texture is written back as it was found
+        assert_eq!(read.content.to_bytes().unwrap(), payload);
+        assert_eq!(parsed.content.to_bytes().unwrap(), payload);
+
+        // nothing is written for an extra block too long to be stored
+        let mut content = read.content;
+        if let AssetContent::Texture { header, .. } = &mut content {
+            header.extra = vec![0; 0x100];
+        }
+        let mut bytes = vec![];
+        assert!(matches!(
+            content.write(&mut bytes),
+            Err(AssetErrors::Overflow(_))
+        ));
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let (sdb, index) = image_database();
+        let mut image = index.get_by_resource_id(75879).unwrap();
+        image.size += 1;
+
+        match AssetData::read(Cursor::new(&sdb), &image) {
+            Err(AssetErrors::SizeMismatch {
+                expected, found, ..
+            }) => {
+                assert_eq!(expected, 5);
+                assert_eq!(found, 4);
+            }
+            _ => panic!("expected a size mismatch error"),
+        }
+    }
+
+    #[test]
+    fn test_lenient_size_mismatch() {
+        let (sdb, index) = image_database();
+        let mut image = index.get_by_resource_id(75879).unwrap();
+        image.size += 1;
+
+        let asset =
+            AssetData::read_with_options(Cursor::new(&sdb), &image, ParseOptions::lenient())
+                .unwrap();
+        assert_eq!(asset.diagnostics.len(), 1);
+        match asset.content {
+            AssetContent::Image { bytes } => assert_eq!(bytes, vec![0xFF, 0xD8, 0xFF, 0xD9]),
+            _ => panic!("expected an image"),
+        }
+    }
+
+    #[test]
+    fn test_truncated_records() {
+        let assets = [
+            AssetData::new(
+                AssetType::Sound,
+                50677,
+                "combat_pain_male_3",
+                AssetContent::Sound {
+                    filename: "combat_pain_male_3.ogg".into(),
+                    size: 4,
+                    bytes: b"OggS".to_vec(),
+                    properties: PropertyBag::new(),
+                },
+            ),
+            AssetData::new(
+                AssetType::Text,
+                59847,
+                "eng_faction_display_name",
+                AssetContent::Text {
+                    contents: vec![TextContent {
+                        refs: vec!["1".into()],
+                        nodes: vec![TextNode::Content("Hola".into())],
+                        empty_lines: vec![],
+                    }],
+                },
+            ),
+        ];
+
+        let (image_sdb, image_index) = image_database();
+        let mut databases = vec![(image_sdb, image_index)];
+        for asset in &assets {
+            let mut sdb = vec![];
+            let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+            database.add(asset).unwrap();
+            let index = database.finish().unwrap();
+            databases.push((sdb, index));
+        }
+
+        // the payload is built by hand, following the texture layout
+        let dds = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        let mut payload = vec![0x10];
+        [0u32, 4, 4, 0]
+            .iter()
+            .for_each(|v| payload.extend_from_slice(&v.to_le_bytes()));
+        payload.push(0x64);
+        payload.extend_from_slice(&[0; 48]);
+        payload.push(0);
+        let dds_start = payload.len();
+        dds.write(&mut payload).unwrap();
+
+        let texture = AssetData::new(
+            AssetType::Texture,
+            1260,
+            "grass",
+            AssetContent::NotSupported,
+        );
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database.add_payload(&texture, &payload).unwrap();
+        let index = database.finish().unwrap();
+
+        // pixel data is read until the end of the stream, only the header is required
+        let bookmark = &index.bookmarks()[0];
+        let header_end = bookmark.node_end + texture.header_length() + dds_start + 128;
+        sdb.truncate(header_end + ASSET_NODE_END.len());
+        databases.push((sdb, index));
+
+        for (sdb, index) in &databases {
+            let bookmark = &index.bookmarks()[0];
+            assert!(AssetData::read(Cursor::new(sdb), bookmark).is_ok());
+            assert!(AssetData::parse(sdb, bookmark).is_ok());
+
+            // every prefix ending before the RIAP string must fail without panicking
+            for length in bookmark.node_end..sdb.len() - ASSET_NODE_END.len() {
+                let read = match AssetData::read(Cursor::new(&sdb[..length]), bookmark) {
+                    Err(AssetErrors::UnexpectedEof { offset }) => offset,
+                    other => panic!(
+                        "expected an unexpected eof error at length {}, found {:?}",
+                        length,
+                        other.err()
+                    ),
+                };
+                assert!(read <= length as u64);
+
+                // slices report the same offset
+                match AssetData::parse(&sdb[..length], bookmark) {
+                    Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, read),
+                    other => panic!(
+                        "expected an unexpected eof error at length {}, found {:?}",
+                        length,
+                        other.err()
+                    ),
+                }
+            }
+        }
+    }
 
     #[test]
     fn test_music_database() {
@@ -153,6 +1010,7 @@ mod tests {
                 filename,
                 bytes: _b,
                 size: _s,
+                ..
             } => {
                 assert_eq!(filename, ("regnum_ignis.ogg"));
             }
@@ -170,6 +1028,7 @@ mod tests {
                 filename,
                 bytes: _b,
                 size: _s,
+                ..
             } => {
                 assert_eq!(filename, ("combat_pain_male_3.ogg"));
             }
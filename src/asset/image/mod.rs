@@ -1,17 +1,13 @@
 pub const JPEG_END: [u8; 2] = [0xFF, 0xD9];
 
-pub fn find_last_sequence(data: &Vec<u8>, sequence: &[u8; 2]) -> Option<usize> {
-    if sequence.len() != 2 || data.len() < 2 {
-        return None; // Sequence must be 2 bytes, and data must have at least 2 bytes
+pub fn find_last_sequence(data: &[u8], sequence: &[u8; 2]) -> Option<usize> {
+    if data.len() < 2 {
+        return None; // data must have at least 2 bytes
     }
 
-    for i in (0..=data.len() - 2).rev() {
-        if data[i] == sequence[0] && data[i + 1] == sequence[1] {
-            return Some(i);
-        }
-    }
-
-    None // Sequence not found
+    (0..=data.len() - 2)
+        .rev()
+        .find(|&i| data[i] == sequence[0] && data[i + 1] == sequence[1])
 }
 
 #[cfg(test)]
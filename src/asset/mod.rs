@@ -2,53 +2,56 @@ pub mod bookmark;
 pub mod content;
 pub mod data;
 pub mod image;
+pub mod property;
 pub mod sound;
 pub mod text;
 pub mod texture;
 
 pub use content::AssetContent;
+pub use property::{Property, PropertyBag, PropertyValue};
+pub use sound::{OggReport, VorbisInfo};
 pub use text::content::{TextContent, TextNode};
 pub use text::WINDOWS_SEPARATOR;
 
 use crate::errors::AssetErrors;
 
 /// Marks the start of the asset node
-pub const ASSET_NODE_START: &'static str = "PAIR";
+pub const ASSET_NODE_START: &str = "PAIR";
 /// Marks the end of the asset node
-pub const ASSET_NODE_END: &'static str = "RIAP";
+pub const ASSET_NODE_END: &str = "RIAP";
 
 /// A string used to identify a Material asset
-pub const ASSET_TYPE_MATERIAL: &'static str = "MATERIAL";
+pub const ASSET_TYPE_MATERIAL: &str = "MATERIAL";
 /// A string used to identify an Animation asset
-pub const ASSET_TYPE_ANIMATION: &'static str = "ANIMATION";
+pub const ASSET_TYPE_ANIMATION: &str = "ANIMATION";
 /// A string used to identify a Mesh asset
-pub const ASSET_TYPE_MESH: &'static str = "MESH";
+pub const ASSET_TYPE_MESH: &str = "MESH";
 /// A string used to identify an Image asset
-pub const ASSET_TYPE_IMAGE: &'static str = "IMAGE";
+pub const ASSET_TYPE_IMAGE: &str = "IMAGE";
 /// A string used to identify a Text asset
-pub const ASSET_TYPE_TEXT: &'static str = "TEXT";
+pub const ASSET_TYPE_TEXT: &str = "TEXT";
 /// A string used to identify a Binary asset
-pub const ASSET_TYPE_BINARY: &'static str = "BINARY";
+pub const ASSET_TYPE_BINARY: &str = "BINARY";
 /// A string used to identify a Texture asset
-pub const ASSET_TYPE_TEXTURE: &'static str = "TEXTURE";
+pub const ASSET_TYPE_TEXTURE: &str = "TEXTURE";
 /// A string used to identify a Font asset
-pub const ASSET_TYPE_FONT: &'static str = "FONT";
+pub const ASSET_TYPE_FONT: &str = "FONT";
 /// A string used to identify an Effect asset
-pub const ASSET_TYPE_EFFECT: &'static str = "EFFECT";
+pub const ASSET_TYPE_EFFECT: &str = "EFFECT";
 /// A string used to identify a Sound asset
-pub const ASSET_TYPE_SOUND: &'static str = "SOUND";
+pub const ASSET_TYPE_SOUND: &str = "SOUND";
 /// A string used to identify a Music asset
-pub const ASSET_TYPE_MUSIC: &'static str = "MUSIC";
+pub const ASSET_TYPE_MUSIC: &str = "MUSIC";
 /// A string used to identify a character mesh
-pub const ASSET_TYPE_CHAR_MESH: &'static str = "mesh";
+pub const ASSET_TYPE_CHAR_MESH: &str = "mesh";
 /// A string used to identify a MapObject asset
-pub const ASSET_TYPE_MAPOBJECT: &'static str = "MAPOBJECT";
+pub const ASSET_TYPE_MAPOBJECT: &str = "MAPOBJECT";
 /// A string used to identify a TerrainRegion asset
-pub const ASSET_TYPE_TERRAIN_REGION: &'static str = "TERRAIN_REGION";
+pub const ASSET_TYPE_TERRAIN_REGION: &str = "TERRAIN_REGION";
 /// A string used to identify a WorldMap asset
-pub const ASSET_TYPE_WORLDMAP: &'static str = "WORLDMAP";
+pub const ASSET_TYPE_WORLDMAP: &str = "WORLDMAP";
 /// ???
-pub const ASSET_TYPE_PCAUTH: &'static str = "pcauth";
+pub const ASSET_TYPE_PCAUTH: &str = "pcauth";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AssetType {
@@ -71,7 +74,7 @@ pub enum AssetType {
 }
 
 impl TryFrom<&str> for AssetType {
-    type Error = anyhow::Error;
+    type Error = AssetErrors;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
@@ -90,32 +93,30 @@ impl TryFrom<&str> for AssetType {
             ASSET_TYPE_MAPOBJECT => Ok(Self::MapObject),
             ASSET_TYPE_TERRAIN_REGION => Ok(Self::TerrainRegion),
             ASSET_TYPE_WORLDMAP => Ok(Self::WorldMap),
-            unknown => Err(anyhow::anyhow!(AssetErrors::UnknownAssetTypeError(
-                unknown.to_owned()
-            ))),
+            unknown => Err(AssetErrors::UnknownAssetTypeError(unknown.to_owned())),
         }
     }
 }
 
-impl Into<&str> for AssetType {
-    fn into(self) -> &'static str {
-        match self {
-            Self::Material => ASSET_TYPE_MATERIAL,
-            Self::Animation => ASSET_TYPE_ANIMATION,
-            Self::Mesh => ASSET_TYPE_MESH,
-            Self::Image => ASSET_TYPE_IMAGE,
-            Self::Text => ASSET_TYPE_TEXT,
-            Self::Binary => ASSET_TYPE_BINARY,
-            Self::Texture => ASSET_TYPE_TEXTURE,
-            Self::Effect => ASSET_TYPE_EFFECT,
-            Self::Font => ASSET_TYPE_FONT,
-            Self::Sound => ASSET_TYPE_SOUND,
-            Self::Music => ASSET_TYPE_MUSIC,
-            Self::Character => ASSET_TYPE_CHAR_MESH,
-            Self::MapObject => ASSET_TYPE_MAPOBJECT,
-            Self::TerrainRegion => ASSET_TYPE_TERRAIN_REGION,
-            Self::WorldMap => ASSET_TYPE_WORLDMAP,
-            Self::Auth => ASSET_TYPE_PCAUTH,
+impl From<AssetType> for &str {
+    fn from(value: AssetType) -> Self {
+        match value {
+            AssetType::Material => ASSET_TYPE_MATERIAL,
+            AssetType::Animation => ASSET_TYPE_ANIMATION,
+            AssetType::Mesh => ASSET_TYPE_MESH,
+            AssetType::Image => ASSET_TYPE_IMAGE,
+            AssetType::Text => ASSET_TYPE_TEXT,
+            AssetType::Binary => ASSET_TYPE_BINARY,
+            AssetType::Texture => ASSET_TYPE_TEXTURE,
+            AssetType::Effect => ASSET_TYPE_EFFECT,
+            AssetType::Font => ASSET_TYPE_FONT,
+            AssetType::Sound => ASSET_TYPE_SOUND,
+            AssetType::Music => ASSET_TYPE_MUSIC,
+            AssetType::Character => ASSET_TYPE_CHAR_MESH,
+            AssetType::MapObject => ASSET_TYPE_MAPOBJECT,
+            AssetType::TerrainRegion => ASSET_TYPE_TERRAIN_REGION,
+            AssetType::WorldMap => ASSET_TYPE_WORLDMAP,
+            AssetType::Auth => ASSET_TYPE_PCAUTH,
         }
     }
 }
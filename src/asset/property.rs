@@ -0,0 +1,480 @@
+use crate::errors::{AssetErrors, Result};
+use crate::parser::{fail, windows_1252, SliceErrorKind, SliceResult};
+use crate::reader::OffsetReader;
+use byteorder::{LittleEndian, WriteBytesExt};
+use encoding_rs::WINDOWS_1252;
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u32, le_u8};
+use std::io::{Read, Write};
+
+/// Flag used by properties holding a string
+pub const PROPERTY_FLAG_STRING: u8 = 0x2;
+/// Flag used by properties holding a buffer
+pub const PROPERTY_FLAG_BUFFER: u8 = 0x4;
+
+/// An enum listing the values a property can hold, identified by the flag found after its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    /// A Windows-1252 string
+    String(String),
+    /// A sequence of bytes, such as an embedded file
+    Buffer(Vec<u8>),
+    /// A flag of unknown meaning, along with the remaining bytes of the list
+    ///
+    /// The length of its value is not known, so reading stops at the first
+    /// unknown flag and keeps everything after it as found.
+    Unknown { flag: u8, bytes: Vec<u8> },
+}
+
+/// A named value found in a list of properties
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+/// A list of properties, as stored in the content of some assets
+///
+/// Each property is stored as a name length, a name and a flag identifying
+/// the type of the value that follows. Properties are kept in the order
+/// they were found. A property with an unknown flag ends the list, as the
+/// length of its value is not known.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertyBag {
+    pub properties: Vec<Property>,
+}
+
+/// A property value borrowing its bytes from the parsed slice
+pub(crate) enum PropertyRef<'a> {
+    String(String),
+    Buffer(&'a [u8]),
+    Unknown { flag: u8, bytes: &'a [u8] },
+}
+
+impl PropertyValue {
+    /// Returns the flag used to store the value
+    pub fn flag(&self) -> u8 {
+        match self {
+            Self::String(_) => PROPERTY_FLAG_STRING,
+            Self::Buffer(_) => PROPERTY_FLAG_BUFFER,
+            Self::Unknown { flag, .. } => *flag,
+        }
+    }
+}
+
+impl From<PropertyRef<'_>> for PropertyValue {
+    fn from(value: PropertyRef<'_>) -> Self {
+        match value {
+            PropertyRef::String(value) => Self::String(value),
+            PropertyRef::Buffer(bytes) => Self::Buffer(bytes.to_vec()),
+            PropertyRef::Unknown { flag, bytes } => Self::Unknown {
+                flag,
+                bytes: bytes.to_vec(),
+            },
+        }
+    }
+}
+
+impl PropertyBag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the first property with the given name
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| &property.value)
+    }
+
+    /// Returns the value of a string property
+    pub fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(PropertyValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a buffer property
+    pub fn get_buffer(&self, name: &str) -> Option<&[u8]> {
+        match self.get(name) {
+            Some(PropertyValue::Buffer(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of a property, appending it if not found
+    pub fn set(&mut self, name: &str, value: PropertyValue) {
+        match self.properties.iter_mut().find(|p| p.name == name) {
+            Some(property) => property.value = value,
+            None => self.properties.push(Property {
+                name: name.into(),
+                value,
+            }),
+        }
+    }
+
+    /// Removes the first property with the given name, returning its value
+    pub fn remove(&mut self, name: &str) -> Option<PropertyValue> {
+        let pos = self.properties.iter().position(|p| p.name == name)?;
+        Some(self.properties.remove(pos).value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Property> {
+        self.properties.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Reads properties until the given number of bytes is consumed
+    pub fn read<T: Read>(reader: &mut OffsetReader<T>, length: u64) -> Result<Self> {
+        Self::read_bounded(reader, length, 0, &[])
+    }
+
+    /// Reads properties until the given number of bytes is consumed
+    ///
+    /// The last property may end up to `tolerance` bytes past that length.
+    /// Properties named in `flags` must use the flag given along with them.
+    pub(crate) fn read_bounded<T: Read>(
+        reader: &mut OffsetReader<T>,
+        length: u64,
+        tolerance: u64,
+        flags: &[(&str, u8)],
+    ) -> Result<Self> {
+        let limit = reader.offset() + length;
+        let mut bag = Self::new();
+
+        while reader.offset() < limit {
+            let name_length = reader.read_u32()?;
+            let name = reader.read_windows_1252(name_length as usize)?;
+
+            let offset = reader.offset();
+            let flag = reader.read_u8()?;
+            Self::expect_flag(flags, &name, flag, offset)?;
+
+            let value = match flag {
+                PROPERTY_FLAG_STRING => {
+                    let value_length = reader.read_u32()?;
+                    PropertyValue::String(reader.read_windows_1252(value_length as usize)?)
+                }
+                PROPERTY_FLAG_BUFFER => {
+                    let value_length = reader.read_u32()?;
+                    PropertyValue::Buffer(reader.read_bytes(value_length as usize)?)
+                }
+                flag => {
+                    let rest = limit.saturating_sub(reader.offset());
+                    let bytes = reader.read_bytes(rest as usize)?;
+                    bag.properties.push(Property {
+                        name,
+                        value: PropertyValue::Unknown { flag, bytes },
+                    });
+                    break;
+                }
+            };
+
+            bag.properties.push(Property { name, value });
+        }
+
+        if reader.offset() > limit + tolerance {
+            return Err(AssetErrors::OutOfBounds {
+                limit: limit + tolerance,
+                offset: reader.offset(),
+            });
+        }
+
+        Ok(bag)
+    }
+
+    /// Checks the flag of a property, when it is one of the given ones
+    fn expect_flag(flags: &[(&str, u8)], name: &str, flag: u8, offset: u64) -> Result<()> {
+        match Self::expected_flag(flags, name) {
+            Some(expected) if expected != flag => Err(AssetErrors::BadMagic {
+                expected: vec![expected],
+                found: vec![flag],
+                offset,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the flag a property must use, if given
+    fn expected_flag(flags: &[(&str, u8)], name: &str) -> Option<u8> {
+        flags
+            .iter()
+            .find(|(expected, _)| *expected == name)
+            .map(|&(_, flag)| flag)
+    }
+
+    /// Parses properties until the given number of bytes is consumed
+    pub fn parse(input: &[u8], length: usize) -> SliceResult<'_, Self> {
+        let mut bag = Self::new();
+        let (input, properties) = Self::parse_properties(input, length, 0, &[])?;
+        for (name, value) in properties {
+            bag.properties.push(Property {
+                name,
+                value: value.into(),
+            });
+        }
+
+        Ok((input, bag))
+    }
+
+    /// Parses properties until the given number of bytes is consumed, borrowing their buffers
+    ///
+    /// Tolerance and flags are handled as done by `read_bounded`.
+    pub(crate) fn parse_properties<'a>(
+        input: &'a [u8],
+        length: usize,
+        tolerance: usize,
+        flags: &[(&str, u8)],
+    ) -> SliceResult<'a, Vec<(String, PropertyRef<'a>)>> {
+        let start = input.len();
+
+        let mut input = input;
+        let mut properties = vec![];
+        while start - input.len() < length {
+            let rest = length - (start - input.len());
+            let (next, (name, value)) = Self::parse_property(input, rest, flags)?;
+            input = next;
+
+            let unknown = matches!(value, PropertyRef::Unknown { .. });
+            properties.push((name, value));
+            if unknown {
+                break;
+            }
+        }
+
+        if start - input.len() > length + tolerance {
+            let limit = start.saturating_sub(length + tolerance);
+            return fail(input, SliceErrorKind::OutOfBounds { limit });
+        }
+
+        Ok((input, properties))
+    }
+
+    /// Parses a property, an unknown flag taking the given number of remaining bytes
+    fn parse_property<'a>(
+        input: &'a [u8],
+        rest: usize,
+        flags: &[(&str, u8)],
+    ) -> SliceResult<'a, (String, PropertyRef<'a>)> {
+        let (input, name_length) = le_u32(input)?;
+        let (input, name) = windows_1252(name_length as usize)(input)?;
+
+        let (after_flag, flag) = le_u8(input)?;
+        if let Some(expected) = Self::expected_flag(flags, &name) {
+            if expected != flag {
+                return fail(
+                    input,
+                    SliceErrorKind::BadMagic {
+                        expected: vec![expected],
+                        found: vec![flag],
+                    },
+                );
+            }
+        }
+
+        let (input, value) = match (after_flag, flag) {
+            (input, PROPERTY_FLAG_STRING) => {
+                let (input, value_length) = le_u32(input)?;
+                let (input, value) = windows_1252(value_length as usize)(input)?;
+                (input, PropertyRef::String(value))
+            }
+            (input, PROPERTY_FLAG_BUFFER) => {
+                let (input, value_length) = le_u32(input)?;
+                let (input, bytes) = take(value_length)(input)?;
+                (input, PropertyRef::Buffer(bytes))
+            }
+            (input, flag) => {
+                let used = 4 + name_length as usize + 1;
+                let (input, bytes) = take(rest.saturating_sub(used))(input)?;
+                (input, PropertyRef::Unknown { flag, bytes })
+            }
+        };
+
+        Ok((input, (name, value)))
+    }
+
+    /// Writes the properties using the same layout expected by `read`
+    ///
+    /// A property with an unknown flag can only be the last one.
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        let unknown = self
+            .properties
+            .iter()
+            .position(|property| matches!(property.value, PropertyValue::Unknown { .. }));
+        if unknown.is_some_and(|pos| pos + 1 != self.properties.len()) {
+            return Err(AssetErrors::UnsupportedContentError(
+                "a property with an unknown flag must be the last one".into(),
+            ));
+        }
+
+        for property in &self.properties {
+            let (name, _, _) = WINDOWS_1252.encode(&property.name);
+            writer.write_u32::<LittleEndian>(u32::try_from(name.len())?)?;
+            writer.write_all(&name)?;
+            writer.write_u8(property.value.flag())?;
+
+            match &property.value {
+                PropertyValue::String(value) => {
+                    let (value, _, _) = WINDOWS_1252.encode(value);
+                    writer.write_u32::<LittleEndian>(u32::try_from(value.len())?)?;
+                    writer.write_all(&value)?;
+                }
+                PropertyValue::Buffer(bytes) => {
+                    writer.write_u32::<LittleEndian>(u32::try_from(bytes.len())?)?;
+                    writer.write_all(bytes)?;
+                }
+                PropertyValue::Unknown { bytes, .. } => writer.write_all(bytes)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PropertyBag, PropertyValue, PROPERTY_FLAG_BUFFER, PROPERTY_FLAG_STRING};
+    use crate::errors::AssetErrors;
+    use crate::parser::into_asset_error;
+    use crate::reader::OffsetReader;
+
+    fn bag() -> PropertyBag {
+        let mut bag = PropertyBag::new();
+        bag.set("filename", PropertyValue::String("Cañada.ogg".into()));
+        bag.set("file_in_buffer", PropertyValue::Buffer(b"OggS".to_vec()));
+        bag.set(
+            "looping",
+            PropertyValue::Unknown {
+                flag: 1,
+                bytes: vec![1, 0, 0, 0],
+            },
+        );
+        bag
+    }
+
+    #[test]
+    fn test_property_roundtrip() {
+        let mut bytes = vec![];
+        bag().write(&mut bytes).unwrap();
+
+        let mut reader = OffsetReader::new(bytes.as_slice());
+        let read = PropertyBag::read(&mut reader, bytes.len() as u64).unwrap();
+        assert_eq!(read, bag());
+        assert_eq!(read.get_string("filename"), Some("Cañada.ogg"));
+        assert_eq!(read.get_buffer("file_in_buffer"), Some(b"OggS".as_slice()));
+        assert_eq!(
+            read.get("looping"),
+            Some(&PropertyValue::Unknown {
+                flag: 1,
+                bytes: vec![1, 0, 0, 0]
+            })
+        );
+
+        let (rest, parsed) = PropertyBag::parse(&bytes, bytes.len()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, bag());
+    }
+
+    #[test]
+    fn test_property_out_of_bounds() {
+        // an unknown flag takes the remaining bytes, so the buffer is the last property
+        let mut bag = bag();
+        bag.remove("looping");
+        let mut bytes = vec![];
+        bag.write(&mut bytes).unwrap();
+        let length = bytes.len() - 2;
+
+        let mut reader = OffsetReader::new(bytes.as_slice());
+        match PropertyBag::read(&mut reader, length as u64) {
+            Err(AssetErrors::OutOfBounds { limit, offset }) => {
+                assert_eq!(limit, length as u64);
+                assert_eq!(offset, bytes.len() as u64);
+            }
+            _ => panic!("expected an out of bounds error"),
+        }
+
+        let err = PropertyBag::parse(&bytes, length).unwrap_err();
+        match into_asset_error(err, &bytes) {
+            AssetErrors::OutOfBounds { limit, offset } => {
+                assert_eq!(limit, length as u64);
+                assert_eq!(offset, bytes.len() as u64);
+            }
+            _ => panic!("expected an out of bounds error"),
+        }
+    }
+
+    #[test]
+    fn test_property_unknown_flag() {
+        let mut bytes = vec![];
+        bag().write(&mut bytes).unwrap();
+        // a string property found after the unknown flag
+        bytes.extend_from_slice(&[4, 0, 0, 0]);
+        bytes.extend_from_slice(b"name\x02");
+
+        // everything after the unknown flag is kept as found
+        let mut reader = OffsetReader::new(bytes.as_slice());
+        let read = PropertyBag::read(&mut reader, bytes.len() as u64).unwrap();
+        assert_eq!(read.len(), 3);
+        match read.get("looping") {
+            Some(PropertyValue::Unknown { flag: 1, bytes }) => {
+                assert_eq!(bytes.len(), 4 + 9);
+                assert!(bytes.ends_with(b"name\x02"));
+            }
+            _ => panic!("expected an unknown flag"),
+        }
+
+        let (rest, parsed) = PropertyBag::parse(&bytes, bytes.len()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, read);
+
+        let mut output = vec![];
+        read.write(&mut output).unwrap();
+        assert_eq!(output, bytes);
+
+        // the unknown flag must be the last property
+        let mut bag = bag();
+        bag.set("volume", PropertyValue::String("0.8".into()));
+        assert!(matches!(
+            bag.write(vec![]),
+            Err(AssetErrors::UnsupportedContentError(_))
+        ));
+    }
+
+    #[test]
+    fn test_property_flags() {
+        let mut bytes = vec![];
+        bag().write(&mut bytes).unwrap();
+        let flags = [("filename", PROPERTY_FLAG_BUFFER)];
+
+        let mut reader = OffsetReader::new(bytes.as_slice());
+        match PropertyBag::read_bounded(&mut reader, bytes.len() as u64, 0, &flags) {
+            Err(AssetErrors::BadMagic {
+                expected,
+                found,
+                offset,
+            }) => {
+                assert_eq!(expected, vec![PROPERTY_FLAG_BUFFER]);
+                assert_eq!(found, vec![PROPERTY_FLAG_STRING]);
+                assert_eq!(offset, 4 + 8);
+            }
+            _ => panic!("expected a bad magic error"),
+        }
+
+        match PropertyBag::parse_properties(&bytes, bytes.len(), 0, &flags) {
+            Err(err) => match into_asset_error(err, &bytes) {
+                AssetErrors::BadMagic { offset, .. } => assert_eq!(offset, 4 + 8),
+                _ => panic!("expected a bad magic error"),
+            },
+            Ok(_) => panic!("expected a bad magic error"),
+        }
+    }
+}
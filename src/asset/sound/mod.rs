@@ -1,2 +1,23 @@
+pub mod ogg;
+#[cfg(feature = "audio")]
+pub mod pcm;
+pub mod vorbis;
+
+pub use ogg::{repair, OggIssue, OggReport};
+#[cfg(feature = "audio")]
+pub use pcm::PcmAudio;
+pub use vorbis::VorbisInfo;
+
+use crate::asset::property::{PROPERTY_FLAG_BUFFER, PROPERTY_FLAG_STRING};
+
 pub const SOUND_ATTR_FILENAME: &str = "filename";
 pub const SOUND_ATTR_FILEINBUFFER: &str = "file_in_buffer";
+/// Flags expected for the attributes holding the filename and the file
+pub const SOUND_FLAGS: [(&str, u8); 2] = [
+    (SOUND_ATTR_FILENAME, PROPERTY_FLAG_STRING),
+    (SOUND_ATTR_FILEINBUFFER, PROPERTY_FLAG_BUFFER),
+];
+/// Number of bytes the attributes of a sound can take past its content size
+///
+/// Kept from the fault tolerant check of the original reader.
+pub const SOUND_SIZE_TOLERANCE: usize = 16;
@@ -0,0 +1,521 @@
+use crate::errors::{AssetErrors, Result};
+use crate::parser::{into_asset_error, magic, SliceResult};
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u32, le_u64, le_u8};
+use nom::Parser;
+
+/// Marks the start of every page in an Ogg stream
+pub const OGG_CAPTURE_PATTERN: &[u8] = b"OggS";
+
+/// Header type flag set on pages continuing a packet from the previous page
+pub const OGG_FLAG_CONTINUED: u8 = 0x1;
+/// Header type flag set on the first page of a stream
+pub const OGG_FLAG_FIRST: u8 = 0x2;
+/// Header type flag set on the last page of a stream
+pub const OGG_FLAG_LAST: u8 = 0x4;
+
+/// Length of a page header, not including its lacing values
+const OGG_HEADER_LENGTH: usize = 27;
+/// Offset of the header type within a page
+const OGG_HEADER_TYPE_OFFSET: usize = 5;
+/// Offset of the checksum within a page
+const OGG_CHECKSUM_OFFSET: usize = 22;
+/// Polynomial used to compute page checksums
+const OGG_CRC_POLYNOMIAL: u32 = 0x04c1_1db7;
+const OGG_CRC_TABLE: [u32; 256] = crc_table();
+
+/// A page of an Ogg stream, borrowing its data from the parsed slice
+#[derive(Debug, Clone)]
+pub struct OggPage<'a> {
+    /// Offset of the page within the stream
+    pub offset: u64,
+    pub version: u8,
+    pub header_type: u8,
+    /// A codec defined position, the number of samples decoded so far for Vorbis
+    pub granule_position: u64,
+    pub serial: u32,
+    pub sequence: u32,
+    pub checksum: u32,
+    /// The lacing values, giving the length of each segment in the page
+    pub segments: &'a [u8],
+    /// The segments of the page
+    pub data: &'a [u8],
+}
+
+/// An issue found while checking the pages of an Ogg stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum OggIssue {
+    /// The stream ends in the middle of a page
+    Truncated { offset: u64 },
+    /// The data found where a page should start is not a page
+    NotAPage { offset: u64 },
+    /// The checksum stored in a page does not match its contents
+    BadChecksum {
+        offset: u64,
+        expected: u32,
+        found: u32,
+    },
+    /// A page does not follow the previous page of its stream
+    BadSequence {
+        offset: u64,
+        expected: u32,
+        found: u32,
+    },
+    /// The last page does not have the end of stream flag set
+    MissingEnd { offset: u64 },
+}
+
+/// The result of checking every page of an Ogg stream
+#[derive(Debug, Clone)]
+pub struct OggReport {
+    /// Number of pages found
+    pub pages: usize,
+    pub issues: Vec<OggIssue>,
+    /// Length of the stream up to the end of the last page found before any issue
+    pub valid_length: usize,
+}
+
+/// A packet rebuilt from the segments of one or more pages
+#[derive(Debug, Clone)]
+pub struct OggPacket {
+    /// Offset of the first byte of the packet within the stream
+    pub offset: u64,
+    pub data: Vec<u8>,
+}
+
+impl<'a> OggPage<'a> {
+    /// Parses a single page, located at the given offset within the stream
+    pub fn parse(input: &'a [u8], offset: u64) -> SliceResult<'a, Self> {
+        let (input, _) = magic(OGG_CAPTURE_PATTERN)(input)?;
+        let (input, (version, header_type, granule_position, serial, sequence, checksum)) =
+            (le_u8, le_u8, le_u64, le_u32, le_u32, le_u32).parse(input)?;
+
+        let (input, segment_count) = le_u8(input)?;
+        let (input, segments) = take(segment_count)(input)?;
+        let length: usize = segments.iter().map(|&s| s as usize).sum();
+        let (input, data) = take(length)(input)?;
+
+        Ok((
+            input,
+            Self {
+                offset,
+                version,
+                header_type,
+                granule_position,
+                serial,
+                sequence,
+                checksum,
+                segments,
+                data,
+            },
+        ))
+    }
+
+    /// Parses every page found in an Ogg stream
+    pub fn parse_all(data: &'a [u8]) -> Result<Vec<Self>> {
+        let mut pages = vec![];
+
+        let mut input = data;
+        while !input.is_empty() {
+            let offset = (data.len() - input.len()) as u64;
+            let (rest, page) =
+                Self::parse(input, offset).map_err(|err| into_asset_error(err, data))?;
+            pages.push(page);
+            input = rest;
+        }
+
+        Ok(pages)
+    }
+
+    /// Returns the number of bytes used by the page
+    pub fn length(&self) -> usize {
+        OGG_HEADER_LENGTH + self.segments.len() + self.data.len()
+    }
+
+    /// Returns the offset of the page data within the stream
+    pub fn data_offset(&self) -> u64 {
+        self.offset + (OGG_HEADER_LENGTH + self.segments.len()) as u64
+    }
+
+    /// Computes the checksum of the page, as it should be stored in its header
+    pub fn compute_checksum(&self) -> u32 {
+        let mut header = OGG_CAPTURE_PATTERN.to_vec();
+        header.push(self.version);
+        header.push(self.header_type);
+        header.extend_from_slice(&self.granule_position.to_le_bytes());
+        header.extend_from_slice(&self.serial.to_le_bytes());
+        header.extend_from_slice(&self.sequence.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.push(self.segments.len() as u8);
+        header.extend_from_slice(self.segments);
+
+        crc(crc(0, &header), self.data)
+    }
+
+    /// Checks whether the stored checksum matches the contents of the page
+    pub fn is_valid(&self) -> bool {
+        self.checksum == self.compute_checksum()
+    }
+
+    pub fn is_continued(&self) -> bool {
+        self.header_type & OGG_FLAG_CONTINUED != 0
+    }
+
+    pub fn is_first(&self) -> bool {
+        self.header_type & OGG_FLAG_FIRST != 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.header_type & OGG_FLAG_LAST != 0
+    }
+}
+
+impl OggReport {
+    /// Checks the checksums and sequence numbers of every page in an Ogg stream
+    ///
+    /// Pages are checked up to the end of the stream or the first data that
+    /// cannot be parsed as a page.
+    pub fn check(data: &[u8]) -> Self {
+        let mut report = Self {
+            pages: 0,
+            issues: vec![],
+            valid_length: 0,
+        };
+
+        // last sequence number found on each stream
+        let mut sequences: Vec<(u32, u32)> = vec![];
+        let mut last: Option<OggPage> = None;
+
+        let mut input = data;
+        while !input.is_empty() {
+            let offset = (data.len() - input.len()) as u64;
+            let page = match OggPage::parse(input, offset) {
+                Ok((rest, page)) => {
+                    input = rest;
+                    page
+                }
+                Err(err) => {
+                    report.issues.push(match into_asset_error(err, data) {
+                        AssetErrors::UnexpectedEof { .. } => OggIssue::Truncated { offset },
+                        _ => OggIssue::NotAPage { offset },
+                    });
+                    break;
+                }
+            };
+            report.pages += 1;
+
+            let found = page.compute_checksum();
+            if page.checksum != found {
+                report.issues.push(OggIssue::BadChecksum {
+                    offset,
+                    expected: page.checksum,
+                    found,
+                });
+            }
+
+            match sequences
+                .iter_mut()
+                .find(|(serial, _)| *serial == page.serial)
+            {
+                Some((_, sequence)) => {
+                    let expected = sequence.wrapping_add(1);
+                    if page.sequence != expected {
+                        report.issues.push(OggIssue::BadSequence {
+                            offset,
+                            expected,
+                            found: page.sequence,
+                        });
+                    }
+                    *sequence = page.sequence;
+                }
+                None => sequences.push((page.serial, page.sequence)),
+            }
+
+            if report.issues.is_empty() {
+                report.valid_length = (page.offset as usize) + page.length();
+            }
+            last = Some(page);
+        }
+
+        if let Some(page) = last.filter(|page| !page.is_last()) {
+            if input.is_empty() {
+                report.issues.push(OggIssue::MissingEnd {
+                    offset: page.offset,
+                });
+            }
+        }
+
+        report
+    }
+
+    /// Checks whether the stream has no issues
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Trims an Ogg stream to the last page found before any issue
+///
+/// The end of stream flag is set on the last page kept, updating its
+/// checksum, so players do not expect more data.
+pub fn repair(data: &[u8]) -> Vec<u8> {
+    let report = OggReport::check(data);
+    let mut bytes = data[..report.valid_length].to_vec();
+
+    // the checksum is computed on the page with its flag already set
+    let last = OggPage::parse_all(&bytes)
+        .ok()
+        .and_then(|pages| pages.last().cloned())
+        .filter(|page| !page.is_last())
+        .map(|mut page| {
+            page.header_type |= OGG_FLAG_LAST;
+            (
+                page.offset as usize,
+                page.header_type,
+                page.compute_checksum(),
+            )
+        });
+
+    if let Some((offset, header_type, checksum)) = last {
+        bytes[offset + OGG_HEADER_TYPE_OFFSET] = header_type;
+        let pos = offset + OGG_CHECKSUM_OFFSET;
+        bytes[pos..pos + 4].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    bytes
+}
+
+/// Updates a page checksum with the given bytes
+pub fn crc(crc: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(crc, |crc, &byte| {
+        (crc << 8) ^ OGG_CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut value = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 0x8000_0000 != 0 {
+                (value << 1) ^ OGG_CRC_POLYNOMIAL
+            } else {
+                value << 1
+            };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+/// Rebuilds the first packets of the stream found on a list of pages
+///
+/// Only pages belonging to the stream of the first page are used. A
+/// packet still unfinished when the pages run out is not returned.
+pub fn packets(pages: &[OggPage], count: usize) -> Vec<OggPacket> {
+    let mut packets = vec![];
+    let Some(serial) = pages.first().map(|page| page.serial) else {
+        return packets;
+    };
+
+    let mut current: Option<OggPacket> = None;
+    for page in pages.iter().filter(|page| page.serial == serial) {
+        let mut pos = 0;
+        for &lacing in page.segments {
+            let segment = &page.data[pos..pos + lacing as usize];
+            let offset = page.data_offset() + pos as u64;
+            pos += lacing as usize;
+
+            let packet = current.get_or_insert_with(|| OggPacket {
+                offset,
+                data: vec![],
+            });
+            packet.data.extend_from_slice(segment);
+
+            // a segment shorter than 255 bytes ends the packet
+            if lacing < 255 {
+                packets.extend(current.take());
+                if packets.len() == count {
+                    return packets;
+                }
+            }
+        }
+    }
+
+    packets
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        crc, packets, repair, OggIssue, OggPage, OggReport, OGG_CAPTURE_PATTERN, OGG_FLAG_FIRST,
+        OGG_FLAG_LAST,
+    };
+    use crate::errors::AssetErrors;
+
+    /// Builds a page holding the given packets, the last one being unfinished if `open` is set
+    ///
+    /// Pages belong to the same stream and hold a valid checksum.
+    pub fn page(
+        header_type: u8,
+        granule_position: u64,
+        sequence: u32,
+        packets: &[&[u8]],
+        open: bool,
+    ) -> Vec<u8> {
+        let mut segments = vec![];
+        let mut data = vec![];
+        for (i, packet) in packets.iter().enumerate() {
+            segments.extend(std::iter::repeat_n(255, packet.len() / 255));
+            if !(open && i + 1 == packets.len()) {
+                segments.push((packet.len() % 255) as u8);
+            }
+            data.extend_from_slice(packet);
+        }
+
+        let mut bytes = OGG_CAPTURE_PATTERN.to_vec();
+        bytes.push(0);
+        bytes.push(header_type);
+        bytes.extend_from_slice(&granule_position.to_le_bytes());
+        bytes.extend_from_slice(&7u32.to_le_bytes());
+        bytes.extend_from_slice(&sequence.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.push(segments.len() as u8);
+        bytes.extend_from_slice(&segments);
+        bytes.extend_from_slice(&data);
+
+        let checksum = crc(0, &bytes);
+        bytes[22..26].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    fn stream() -> Vec<u8> {
+        let mut data = page(OGG_FLAG_FIRST, 0, 0, &[b"first"], false);
+        data.extend(page(0, 1024, 1, &[&[1; 300]], false));
+        data.extend(page(0, 2048, 2, &[&[2; 100]], false));
+        data.extend(page(OGG_FLAG_LAST, 4096, 3, &[b"last"], false));
+        data
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        let long = vec![1; 300];
+        let mut data = page(OGG_FLAG_FIRST, 0, 0, &[b"first", &long[..255]], true);
+        let second = data.len();
+        data.extend(page(0x1, 0, 1, &[&long[255..], b"third"], false));
+        data.extend(page(OGG_FLAG_LAST, 4410, 2, &[b"last"], false));
+
+        let pages = OggPage::parse_all(&data).unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(pages[0].is_first());
+        assert!(pages[1].is_continued());
+        assert!(pages[2].is_last());
+        assert_eq!(pages[1].offset as usize, second);
+        assert_eq!(pages[2].granule_position, 4410);
+
+        // the second packet spans two pages
+        let packets = packets(&pages, 3);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].data, b"first");
+        assert_eq!(packets[1].data, long);
+        assert_eq!(packets[1].offset, pages[0].data_offset() + 5);
+        assert_eq!(packets[2].data, b"third");
+
+        match OggPage::parse_all(&data[..data.len() - 1]) {
+            Err(AssetErrors::UnexpectedEof { offset }) => {
+                assert_eq!(offset, pages[2].data_offset())
+            }
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(crc(0, b"123456789"), 0x89A1_897F);
+
+        let data = stream();
+        let report = OggReport::check(&data);
+        assert!(report.is_valid());
+        assert_eq!(report.pages, 4);
+        assert_eq!(report.valid_length, data.len());
+        assert!(OggPage::parse_all(&data)
+            .unwrap()
+            .iter()
+            .all(|p| p.is_valid()));
+        assert_eq!(repair(&data), data);
+    }
+
+    #[test]
+    fn test_check_corrupted() {
+        let data = stream();
+        let pages: Vec<_> = OggPage::parse_all(&data).unwrap();
+        let third = pages[2].offset as usize;
+
+        // a damaged byte in the third page
+        let mut damaged = data.clone();
+        damaged[pages[2].data_offset() as usize + 10] ^= 0xFF;
+        let report = OggReport::check(&damaged);
+        assert_eq!(report.pages, 4);
+        assert_eq!(report.valid_length, third);
+        match report.issues.as_slice() {
+            [OggIssue::BadChecksum {
+                offset, expected, ..
+            }] => {
+                assert_eq!(*offset as usize, third);
+                assert_eq!(*expected, pages[2].checksum);
+            }
+            issues => panic!("unexpected issues {:?}", issues),
+        }
+
+        // the stream is trimmed and the last page kept ends it
+        let repaired = repair(&damaged);
+        assert_eq!(repaired.len(), third);
+        let report = OggReport::check(&repaired);
+        assert!(report.is_valid());
+        assert!(OggPage::parse_all(&repaired).unwrap()[1].is_last());
+
+        // a missing page
+        let mut missing = data[..third].to_vec();
+        missing.extend_from_slice(&data[pages[3].offset as usize..]);
+        let report = OggReport::check(&missing);
+        assert_eq!(report.valid_length, third);
+        assert_eq!(
+            report.issues,
+            vec![OggIssue::BadSequence {
+                offset: third as u64,
+                expected: 2,
+                found: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_truncated() {
+        let data = stream();
+        let last = OggPage::parse_all(&data).unwrap()[3].offset;
+
+        let report = OggReport::check(&data[..data.len() - 2]);
+        assert_eq!(report.pages, 3);
+        assert_eq!(report.valid_length as u64, last);
+        assert_eq!(report.issues, vec![OggIssue::Truncated { offset: last }]);
+
+        // garbage after the pages
+        let mut garbage = data[..last as usize].to_vec();
+        garbage.extend_from_slice(b"RIAP");
+        let report = OggReport::check(&garbage);
+        assert_eq!(report.issues, vec![OggIssue::NotAPage { offset: last }]);
+
+        // a stream cut at a page boundary does not end properly
+        let report = OggReport::check(&data[..last as usize]);
+        assert_eq!(report.valid_length as u64, last);
+        assert_eq!(report.issues.len(), 1);
+        assert!(matches!(report.issues[0], OggIssue::MissingEnd { .. }));
+
+        let repaired = repair(&data[..data.len() - 2]);
+        assert!(OggReport::check(&repaired).is_valid());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::errors::Result;
+use byteorder::{LittleEndian, WriteBytesExt};
+use lewton::inside_ogg::OggStreamReader;
+use std::io::{Cursor, Write};
+use std::time::Duration;
+
+/// Bits used by each decoded sample
+const PCM_BITS_PER_SAMPLE: u16 = 16;
+/// Format tag used by WAV files holding integer samples
+const WAV_FORMAT_PCM: u16 = 1;
+
+/// Audio decoded from an Ogg Vorbis file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcmAudio {
+    pub channels: u16,
+    pub sample_rate: u32,
+    /// 16 bit samples, interleaved by channel
+    pub samples: Vec<i16>,
+}
+
+impl PcmAudio {
+    /// Decodes an Ogg Vorbis file, such as the one found in a sound asset
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = OggStreamReader::new(Cursor::new(bytes))?;
+
+        let mut samples = vec![];
+        while let Some(packet) = reader.read_dec_packet_itl()? {
+            samples.extend(packet);
+        }
+
+        Ok(Self {
+            channels: reader.ident_hdr.audio_channels.into(),
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            samples,
+        })
+    }
+
+    /// Returns the playing time of the audio
+    pub fn duration(&self) -> Duration {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        let frames = self.samples.len() as f64 / self.channels as f64;
+        Duration::from_secs_f64(frames / self.sample_rate as f64)
+    }
+
+    /// Writes the audio as a WAV file
+    ///
+    /// Sizes are computed with checked arithmetic, so audio too large for
+    /// a WAV file returns an `Overflow` error instead of a broken header.
+    pub fn write_wav<T: Write>(&self, mut writer: T) -> Result<()> {
+        let sample_length = u64::from(PCM_BITS_PER_SAMPLE / 8);
+        let block_align = u16::try_from(u64::from(self.channels) * sample_length)?;
+        let byte_rate = u32::try_from(u64::from(self.sample_rate) * u64::from(block_align))?;
+        let data_length = u32::try_from(self.samples.len() as u64 * sample_length)?;
+        let riff_length = u32::try_from(36 + u64::from(data_length))?;
+
+        // RIFF header
+        writer.write_all(b"RIFF")?;
+        writer.write_u32::<LittleEndian>(riff_length)?;
+        writer.write_all(b"WAVE")?;
+
+        // format chunk
+        writer.write_all(b"fmt ")?;
+        writer.write_u32::<LittleEndian>(16)?;
+        writer.write_u16::<LittleEndian>(WAV_FORMAT_PCM)?;
+        writer.write_u16::<LittleEndian>(self.channels)?;
+        writer.write_u32::<LittleEndian>(self.sample_rate)?;
+        writer.write_u32::<LittleEndian>(byte_rate)?;
+        writer.write_u16::<LittleEndian>(block_align)?;
+        writer.write_u16::<LittleEndian>(PCM_BITS_PER_SAMPLE)?;
+
+        // data chunk
+        writer.write_all(b"data")?;
+        writer.write_u32::<LittleEndian>(data_length)?;
+        for &sample in &self.samples {
+            writer.write_i16::<LittleEndian>(sample)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PcmAudio;
+    use crate::asset::sound::ogg::tests::page;
+    use crate::asset::sound::ogg::{OGG_FLAG_FIRST, OGG_FLAG_LAST};
+    use crate::asset::sound::vorbis::tests::vorbis_file;
+    use crate::errors::AssetErrors;
+    use std::time::Duration;
+
+    /// Packs values into bytes, starting from the least significant bit, as done by Vorbis
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn write(&mut self, value: u32, count: usize) {
+            for bit in 0..count {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                if value >> bit & 1 == 1 {
+                    *self.bytes.last_mut().unwrap() |= 1 << (self.bits % 8);
+                }
+                self.bits += 1;
+            }
+        }
+
+        fn write_bytes(&mut self, bytes: &[u8]) {
+            bytes.iter().for_each(|&byte| self.write(byte.into(), 8));
+        }
+    }
+
+    /// Builds a mono Ogg Vorbis file holding the given number of silent audio packets
+    ///
+    /// The setup header is the smallest one a decoder accepts: a single
+    /// codebook, floor, residue, mapping and mode, using 256 sample blocks.
+    /// Each audio packet marks the floor of its channel as unused, so it
+    /// decodes to silence.
+    fn silent_vorbis_file(packets: usize) -> Vec<u8> {
+        let mut identification = vec![1];
+        identification.extend_from_slice(b"vorbis");
+        identification.extend_from_slice(&0u32.to_le_bytes());
+        identification.push(1);
+        identification.extend_from_slice(&22050u32.to_le_bytes());
+        identification.extend_from_slice(&0i32.to_le_bytes());
+        identification.extend_from_slice(&64000i32.to_le_bytes());
+        identification.extend_from_slice(&0i32.to_le_bytes());
+        identification.extend_from_slice(&[0x88, 1]);
+
+        let mut comment = vec![3];
+        comment.extend_from_slice(b"vorbis");
+        comment.extend_from_slice(&0u32.to_le_bytes());
+        comment.extend_from_slice(&0u32.to_le_bytes());
+        comment.push(1);
+
+        let mut setup = BitWriter::default();
+        setup.write_bytes(b"\x05vorbis");
+        // a codebook with two entries of one bit, without lookup table
+        setup.write(0, 8);
+        setup.write(0x564342, 24);
+        setup.write(1, 16);
+        setup.write(2, 24);
+        setup.write(0, 2);
+        setup.write(0, 5);
+        setup.write(0, 5);
+        setup.write(0, 4);
+        // time domain transforms
+        setup.write(0, 6);
+        setup.write(0, 16);
+        // a type 1 floor without partitions
+        setup.write(0, 6);
+        setup.write(1, 16);
+        setup.write(0, 5);
+        setup.write(0, 2);
+        setup.write(8, 4);
+        // a type 0 residue without classifications books
+        setup.write(0, 6);
+        setup.write(0, 16);
+        setup.write(0, 24);
+        setup.write(0, 24);
+        setup.write(31, 24);
+        setup.write(0, 6);
+        setup.write(0, 8);
+        setup.write(0, 4);
+        // a mapping using the floor and the residue
+        setup.write(0, 6);
+        setup.write(0, 16);
+        setup.write(0, 4);
+        setup.write(0, 24);
+        // a mode using short blocks
+        setup.write(0, 6);
+        setup.write(0, 1);
+        setup.write(0, 32);
+        setup.write(0, 8);
+        // framing bit
+        setup.write(1, 1);
+
+        // audio packet, mode 0, unused floor
+        let audio = [0u8];
+        let audio: Vec<&[u8]> = vec![&audio; packets];
+        let samples = (packets.saturating_sub(1) * 128) as u64;
+
+        let mut data = page(OGG_FLAG_FIRST, 0, 0, &[&identification], false);
+        data.extend(page(0, 0, 1, &[&comment, &setup.bytes], false));
+        data.extend(page(OGG_FLAG_LAST, samples, 2, &audio, false));
+        data
+    }
+
+    #[test]
+    fn test_write_wav() {
+        let audio = PcmAudio {
+            channels: 2,
+            sample_rate: 22050,
+            samples: vec![1, -1, 256, -256],
+        };
+        assert_eq!(audio.duration(), Duration::from_secs_f64(2.0 / 22050.0));
+
+        let mut wav = vec![];
+        audio.write_wav(&mut wav).unwrap();
+        assert_eq!(wav.len(), 44 + 8);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[4..8], &44u32.to_le_bytes());
+        assert_eq!(&wav[22..24], &2u16.to_le_bytes());
+        assert_eq!(&wav[28..32], &88200u32.to_le_bytes());
+        assert_eq!(&wav[40..44], &8u32.to_le_bytes());
+        assert_eq!(&wav[44..], &[1, 0, 0xFF, 0xFF, 0, 1, 0, 0xFF]);
+    }
+
+    #[test]
+    fn test_decode() {
+        let audio = PcmAudio::decode(&silent_vorbis_file(5)).unwrap();
+        assert_eq!(audio.channels, 1);
+        assert_eq!(audio.sample_rate, 22050);
+        // every packet but the first one gives half a block
+        assert_eq!(audio.samples.len(), 4 * 128);
+        assert!(audio.samples.iter().all(|&sample| sample == 0));
+        assert_eq!(audio.duration(), Duration::from_secs_f64(512.0 / 22050.0));
+
+        let mut wav = vec![];
+        audio.write_wav(&mut wav).unwrap();
+        assert_eq!(wav.len(), 44 + 1024);
+    }
+
+    #[test]
+    fn test_write_wav_overflow() {
+        for (channels, sample_rate) in [(u16::MAX, 44100), (2, u32::MAX)] {
+            let audio = PcmAudio {
+                channels,
+                sample_rate,
+                samples: vec![],
+            };
+            assert!(matches!(
+                audio.write_wav(vec![]),
+                Err(AssetErrors::Overflow(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // headers are found, but the setup header is not valid
+        match PcmAudio::decode(&vorbis_file(88200)) {
+            Err(AssetErrors::VorbisError(_)) => {}
+            _ => panic!("expected a vorbis error"),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use super::ogg::{packets, OggPacket, OggPage};
+use crate::errors::{AssetErrors, Result};
+use crate::parser::{into_asset_error, magic, SliceResult};
+use nom::bytes::complete::take;
+use nom::number::complete::{le_i32, le_u32, le_u8};
+use nom::Parser;
+use std::time::Duration;
+
+/// Identifies the headers of a Vorbis stream, after the packet type
+pub const VORBIS_SIGNATURE: &[u8] = b"vorbis";
+
+/// Packet type of the identification header
+const VORBIS_IDENTIFICATION: u8 = 0x1;
+/// Packet type of the comment header
+const VORBIS_COMMENT: u8 = 0x3;
+
+/// The format of an Ogg Vorbis file, read from its headers
+#[derive(Debug, Clone, PartialEq)]
+pub struct VorbisInfo {
+    pub channels: u8,
+    pub sample_rate: u32,
+    /// Bitrates in bits per second, zero or negative when not set
+    pub bitrate_maximum: i32,
+    pub bitrate_nominal: i32,
+    pub bitrate_minimum: i32,
+    /// Number of samples per channel, taken from the last granule position
+    pub samples: u64,
+    /// The encoder name, found in the comment header
+    pub vendor: String,
+    /// Comments found in the comment header, as `(name, value)` pairs
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisInfo {
+    /// Parses the headers of an Ogg Vorbis file
+    ///
+    /// Errors found within a header are located at the start of the
+    /// packet holding it.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let pages = OggPage::parse_all(data)?;
+        let packets = packets(&pages, 2);
+
+        let identification = packets.first().ok_or(AssetErrors::UnexpectedEof {
+            offset: data.len() as u64,
+        })?;
+        let comment = packets.get(1).ok_or(AssetErrors::UnexpectedEof {
+            offset: data.len() as u64,
+        })?;
+
+        let mut info = Self::decode(identification, Self::parse_identification)?;
+        (info.vendor, info.comments) = Self::decode(comment, Self::parse_comment)?;
+
+        // the last page of the stream holds the total number of samples
+        let serial = pages[0].serial;
+        info.samples = pages
+            .iter()
+            .rev()
+            .filter(|page| page.serial == serial)
+            .map(|page| page.granule_position)
+            .find(|&granule| granule != u64::MAX)
+            .unwrap_or(0);
+
+        Ok(info)
+    }
+
+    /// Returns the playing time of the file
+    pub fn duration(&self) -> Duration {
+        if self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(self.samples as f64 / self.sample_rate as f64)
+    }
+
+    /// Returns the value of the first comment with the given name, ignoring case
+    pub fn comment(&self, name: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Runs a parser over a packet, locating errors at the start of the packet
+    fn decode<O>(packet: &OggPacket, parser: fn(&[u8]) -> SliceResult<'_, O>) -> Result<O> {
+        parser(&packet.data).map(|(_, value)| value).map_err(|err| {
+            match into_asset_error(err, &packet.data) {
+                AssetErrors::BadMagic {
+                    expected, found, ..
+                } => AssetErrors::BadMagic {
+                    expected,
+                    found,
+                    offset: packet.offset,
+                },
+                AssetErrors::UnexpectedEof { .. } => AssetErrors::UnexpectedEof {
+                    offset: packet.offset,
+                },
+                err => err,
+            }
+        })
+    }
+
+    /// Parses the identification header, leaving the remaining values empty
+    fn parse_identification(input: &[u8]) -> SliceResult<'_, Self> {
+        let (input, _) = magic(&[VORBIS_IDENTIFICATION])(input)?;
+        let (input, _) = magic(VORBIS_SIGNATURE)(input)?;
+        let (input, _version) = le_u32(input)?;
+
+        let (input, (channels, sample_rate, bitrate_maximum, bitrate_nominal, bitrate_minimum)) =
+            (le_u8, le_u32, le_i32, le_i32, le_i32).parse(input)?;
+
+        Ok((
+            input,
+            Self {
+                channels,
+                sample_rate,
+                bitrate_maximum,
+                bitrate_nominal,
+                bitrate_minimum,
+                samples: 0,
+                vendor: String::new(),
+                comments: vec![],
+            },
+        ))
+    }
+
+    fn parse_comment(input: &[u8]) -> SliceResult<'_, (String, Vec<(String, String)>)> {
+        let (input, _) = magic(&[VORBIS_COMMENT])(input)?;
+        let (input, _) = magic(VORBIS_SIGNATURE)(input)?;
+
+        let (input, vendor) = Self::parse_string(input)?;
+        let (mut input, count) = le_u32(input)?;
+
+        let mut comments = vec![];
+        for _ in 0..count {
+            let (rest, comment) = Self::parse_string(input)?;
+            input = rest;
+
+            let (name, value) = comment.split_once('=').unwrap_or((&comment, ""));
+            comments.push((name.to_string(), value.to_string()));
+        }
+
+        Ok((input, (vendor, comments)))
+    }
+
+    /// Parses a length prefixed string, replacing invalid UTF-8 sequences
+    fn parse_string(input: &[u8]) -> SliceResult<'_, String> {
+        let (input, length) = le_u32(input)?;
+        let (input, bytes) = take(length)(input)?;
+        Ok((input, String::from_utf8_lossy(bytes).into_owned()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::VorbisInfo;
+    use crate::asset::sound::ogg::tests::page;
+    use crate::asset::sound::ogg::{OGG_FLAG_FIRST, OGG_FLAG_LAST};
+    use crate::errors::AssetErrors;
+    use std::time::Duration;
+
+    /// Builds a small Ogg Vorbis file, holding headers but no audio
+    pub fn vorbis_file(samples: u64) -> Vec<u8> {
+        let mut identification = vec![1];
+        identification.extend_from_slice(b"vorbis");
+        identification.extend_from_slice(&0u32.to_le_bytes());
+        identification.push(2);
+        identification.extend_from_slice(&44100u32.to_le_bytes());
+        identification.extend_from_slice(&0i32.to_le_bytes());
+        identification.extend_from_slice(&128000i32.to_le_bytes());
+        identification.extend_from_slice(&0i32.to_le_bytes());
+        identification.extend_from_slice(&[0xB8, 1]);
+
+        let mut comment = vec![3];
+        comment.extend_from_slice(b"vorbis");
+        let vendor = b"Xiph.Org libVorbis I 20020717";
+        comment.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        comment.extend_from_slice(vendor);
+        comment.extend_from_slice(&2u32.to_le_bytes());
+        for entry in [b"TITLE=Cancion de Syrtis".as_slice(), b"ARTIST=NGD"] {
+            comment.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            comment.extend_from_slice(entry);
+        }
+        comment.push(1);
+
+        let mut data = page(OGG_FLAG_FIRST, 0, 0, &[&identification], false);
+        data.extend(page(0, 0, 1, &[&comment, b"\x05vorbis"], false));
+        data.extend(page(OGG_FLAG_LAST, samples, 2, &[&[0; 16]], false));
+        data
+    }
+
+    #[test]
+    fn test_vorbis_info() {
+        let info = VorbisInfo::parse(&vorbis_file(88200)).unwrap();
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.bitrate_nominal, 128000);
+        assert_eq!(info.samples, 88200);
+        assert_eq!(info.duration(), Duration::from_secs(2));
+        assert_eq!(info.vendor, "Xiph.Org libVorbis I 20020717");
+        assert_eq!(info.comment("title"), Some("Cancion de Syrtis"));
+        assert_eq!(info.comment("artist"), Some("NGD"));
+    }
+
+    #[test]
+    fn test_vorbis_bad_header() {
+        let mut data = vorbis_file(88200);
+        // codec signature of the identification header
+        let pos = 28 + 1;
+        data[pos] = b'V';
+
+        match VorbisInfo::parse(&data) {
+            Err(AssetErrors::BadMagic { found, offset, .. }) => {
+                assert_eq!(found, b"Vorbis");
+                assert_eq!(offset, 28);
+            }
+            _ => panic!("expected a bad magic error"),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use super::parse::{TEXT_END, TEXT_START};
+
 /// An enum able to hold different variants of text content
 #[derive(Debug, Clone)]
 pub enum TextNode {
@@ -13,9 +15,23 @@ pub enum TextNode {
     Content(String),
 }
 
+impl std::fmt::Display for TextNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Start => write!(f, "{}", TEXT_START),
+            Self::End => write!(f, "{}", TEXT_END),
+            Self::Stage(stage) => write!(f, "[{}]", stage),
+            Self::Topic(topic) => write!(f, "[{}]", topic),
+            Self::Content(content) => write!(f, "{}", content),
+        }
+    }
+}
+
 /// A wrapper holding a list of identifiers and text nodes
 #[derive(Debug, Clone)]
 pub struct TextContent {
     pub refs: Vec<String>,
     pub nodes: Vec<TextNode>,
+    /// Line numbers of the empty lines left out of `nodes`, so the text is written back as found
+    pub empty_lines: Vec<usize>,
 }
@@ -27,20 +27,51 @@ pub fn delimited_digit(input: &str) -> IResult<&str, &str> {
 }
 
 /// Parses a text into a variant of TextContent
+///
+/// Stages and topics must take the whole line, so writing the node back
+/// gives the same text. Any other line is kept as content.
 pub fn parse_text(text: &str) -> TextNode {
-    if text.trim() == TEXT_START {
+    if text == TEXT_START {
         return TextNode::Start;
     }
 
-    if text.trim() == TEXT_END {
+    if text == TEXT_END {
         return TextNode::End;
     }
 
-    match delimited_digit(text) {
-        Ok((_, d)) => TextNode::Stage(d.parse().unwrap()),
-        Err(_) => match delimited_text(text) {
-            Ok((_, t)) => TextNode::Topic(t.into()),
-            Err(_) => TextNode::Content(text.to_string()),
-        },
+    if let Ok(("", d)) = delimited_digit(text) {
+        match d.parse::<u32>() {
+            Ok(stage) if stage.to_string() == d => return TextNode::Stage(stage),
+            _ => {}
+        }
+    }
+
+    match delimited_text(text) {
+        Ok(("", t)) => TextNode::Topic(t.into()),
+        _ => TextNode::Content(text.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_text;
+    use crate::asset::TextNode;
+
+    #[test]
+    fn test_parse_text() {
+        assert!(matches!(parse_text("[start]"), TextNode::Start));
+        assert!(matches!(parse_text("[3]"), TextNode::Stage(3)));
+        assert!(matches!(parse_text("[Greeting]"), TextNode::Topic(t) if t == "Greeting"));
+
+        // stages that would not be written back as found are kept as topics
+        assert!(matches!(parse_text("[03]"), TextNode::Topic(t) if t == "03"));
+
+        // any other line is kept as content
+        for text in ["", " [start]", "[3] Hola", "[]", "[Greeting] Hola"] {
+            match parse_text(text) {
+                TextNode::Content(content) => assert_eq!(content, text),
+                node => panic!("expected content for {:?}, found {:?}", text, node),
+            }
+        }
     }
 }
@@ -0,0 +1,66 @@
+/// Length of the values starting a texture: an unknown value, the width, the height and another unknown value
+pub const TEXTURE_HEADER_LENGTH: u8 = 0x10;
+/// A byte found after the dimensions of every texture
+pub const TEXTURE_SETTINGS_MARKER: u8 = 0x64;
+/// Length of the block found after the settings marker
+pub const TEXTURE_SETTINGS_LENGTH: usize = 48;
+
+/// The values found before the DDS file of a texture, other than its dimensions
+///
+/// The meaning of these values is not known yet. They are kept as found,
+/// so a texture is written back exactly as it was read.
+///
+/// No value could be matched to a wrap mode, a filtering mode, an alpha flag
+/// or a usage hint, so no named field is provided. The alpha mode and pixel
+/// format are not taken from here, as the DDS header already declares them.
+/// Named accessors belong here once a value is confirmed against game files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureHeader {
+    /// The value found before the width
+    pub unknown1: u32,
+    /// The value found after the height
+    pub unknown2: u32,
+    /// A block of 48 bytes found after the settings marker, likely holding engine side settings
+    pub settings: [u8; TEXTURE_SETTINGS_LENGTH],
+    /// A length prefixed block found right before the DDS file
+    pub extra: Vec<u8>,
+}
+
+impl TextureHeader {
+    /// Returns the settings block as a list of little endian values
+    pub fn settings_values(&self) -> Vec<u32> {
+        self.settings
+            .chunks_exact(4)
+            .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .collect()
+    }
+}
+
+impl Default for TextureHeader {
+    fn default() -> Self {
+        Self {
+            unknown1: 0,
+            unknown2: 0,
+            settings: [0; TEXTURE_SETTINGS_LENGTH],
+            extra: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextureHeader;
+
+    #[test]
+    fn test_settings_values() {
+        let mut header = TextureHeader::default();
+        header.settings[4] = 0x01;
+        header.settings[47] = 0x80;
+
+        let values = header.settings_values();
+        assert_eq!(values.len(), 12);
+        assert_eq!(values[0], 0);
+        assert_eq!(values[1], 1);
+        assert_eq!(values[11], 0x8000_0000);
+    }
+}
@@ -1,16 +1,37 @@
+pub mod header;
+#[cfg(feature = "image")]
+pub mod rgba;
+pub mod surface;
+
+pub use header::TextureHeader;
+#[cfg(feature = "image")]
+pub use rgba::{PixelFormat, RgbaImage};
+pub use surface::{CubemapFace, SurfaceFormat, TextureSurface};
+
 /// String header indicating the beginning of a DDS texture
 pub const DDS_STRING: &str = "DDS ";
 
-// TODO: add as feature?
-// use itertools::Itertools;
-// pub fn barg_to_rgba(data: Vec<u8>) -> Vec<u8> {
-//     let mut out = Vec::with_capacity(data.len());
-//     let mut it = data.into_iter().tuples::<(u8, u8, u8, u8)>();
+/// Reorders pixels stored as B, G, R, A bytes to R, G, B, A
+///
+/// Trailing bytes not making a whole pixel are dropped.
+#[cfg(feature = "image")]
+pub fn bgra_to_rgba(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for pixel in data.chunks_exact(4) {
+        let [b, g, r, a] = [pixel[0], pixel[1], pixel[2], pixel[3]];
+        out.extend_from_slice(&[r, g, b, a]);
+    }
+
+    out
+}
 
-//     while let Some(x) = it.next() {
-//         let (b, a, r, g) = x;
-//         out.extend_from_slice(&[r, g, b, a]);
-//     }
+#[cfg(all(test, feature = "image"))]
+mod tests {
+    use super::bgra_to_rgba;
 
-//     out
-// }
+    #[test]
+    fn test_swizzle() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        assert_eq!(bgra_to_rgba(&data), vec![3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+}
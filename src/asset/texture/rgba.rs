@@ -0,0 +1,447 @@
+use super::bgra_to_rgba;
+use crate::errors::{AssetErrors, Result};
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use std::io::Write;
+
+/// Bytes used by each decoded pixel
+const RGBA_PIXEL_LENGTH: usize = 4;
+
+/// An enum listing the surface formats that can be decoded to RGBA
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PixelFormat {
+    /// DXT1, 4x4 blocks of 8 bytes with 1 bit alpha
+    Bc1,
+    /// DXT3, 4x4 blocks of 16 bytes with explicit 4 bit alpha
+    Bc2,
+    /// DXT5, 4x4 blocks of 16 bytes with interpolated alpha
+    Bc3,
+    /// DXT2, stored as BC2 with colors premultiplied by alpha
+    Dxt2,
+    /// DXT4, stored as BC3 with colors premultiplied by alpha
+    Dxt4,
+    /// 4x4 blocks of 8 bytes holding a single channel, decoded as grayscale
+    Bc4,
+    /// 4x4 blocks of 16 bytes holding two channels, decoded as red and green
+    Bc5,
+    /// Uncompressed B, G, R, A bytes
+    Bgra,
+    /// Uncompressed B, G, R bytes followed by an unused byte
+    Bgrx,
+    /// Uncompressed R, G, B, A bytes
+    Rgba,
+    /// Uncompressed R, G, B bytes followed by an unused byte
+    Rgbx,
+}
+
+/// An image decoded to 8 bit R, G, B, A pixels, stored by rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl PixelFormat {
+    /// Retrieves the format of a surface, if it can be decoded
+    pub fn from_dds(dds: &Dds) -> Result<Self> {
+        if let Some(format) = dds.get_dxgi_format() {
+            return match format {
+                DxgiFormat::BC1_Typeless | DxgiFormat::BC1_UNorm | DxgiFormat::BC1_UNorm_sRGB => {
+                    Ok(Self::Bc1)
+                }
+                DxgiFormat::BC2_Typeless | DxgiFormat::BC2_UNorm | DxgiFormat::BC2_UNorm_sRGB => {
+                    Ok(Self::Bc2)
+                }
+                DxgiFormat::BC3_Typeless | DxgiFormat::BC3_UNorm | DxgiFormat::BC3_UNorm_sRGB => {
+                    Ok(Self::Bc3)
+                }
+                DxgiFormat::BC4_Typeless | DxgiFormat::BC4_UNorm => Ok(Self::Bc4),
+                DxgiFormat::BC5_Typeless | DxgiFormat::BC5_UNorm => Ok(Self::Bc5),
+                DxgiFormat::B8G8R8A8_Typeless
+                | DxgiFormat::B8G8R8A8_UNorm
+                | DxgiFormat::B8G8R8A8_UNorm_sRGB => Ok(Self::Bgra),
+                DxgiFormat::B8G8R8X8_Typeless
+                | DxgiFormat::B8G8R8X8_UNorm
+                | DxgiFormat::B8G8R8X8_UNorm_sRGB => Ok(Self::Bgrx),
+                DxgiFormat::R8G8B8A8_Typeless
+                | DxgiFormat::R8G8B8A8_UNorm
+                | DxgiFormat::R8G8B8A8_UNorm_sRGB => Ok(Self::Rgba),
+                format => Err(AssetErrors::UnsupportedTextureFormat(format!(
+                    "{:?}",
+                    format
+                ))),
+            };
+        }
+
+        match dds.get_d3d_format() {
+            Some(D3DFormat::DXT1) => Ok(Self::Bc1),
+            Some(D3DFormat::DXT2) => Ok(Self::Dxt2),
+            Some(D3DFormat::DXT3) => Ok(Self::Bc2),
+            Some(D3DFormat::DXT4) => Ok(Self::Dxt4),
+            Some(D3DFormat::DXT5) => Ok(Self::Bc3),
+            Some(D3DFormat::A8R8G8B8) => Ok(Self::Bgra),
+            Some(D3DFormat::X8R8G8B8) => Ok(Self::Bgrx),
+            Some(D3DFormat::A8B8G8R8) => Ok(Self::Rgba),
+            Some(D3DFormat::X8B8G8R8) => Ok(Self::Rgbx),
+            Some(format) => Err(AssetErrors::UnsupportedTextureFormat(format!(
+                "{:?}",
+                format
+            ))),
+            None => Err(AssetErrors::UnsupportedTextureFormat(format!(
+                "{:?}",
+                dds.header.spf
+            ))),
+        }
+    }
+
+    /// Returns the bytes used by each 4x4 block, if the format is block compressed
+    pub fn block_length(&self) -> Option<usize> {
+        match self {
+            Self::Bc1 | Self::Bc4 => Some(8),
+            Self::Bc2 | Self::Bc3 | Self::Bc5 | Self::Dxt2 | Self::Dxt4 => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes used by a surface of the given dimensions
+    pub fn data_length(&self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        match self.block_length() {
+            Some(length) => width.div_ceil(4) * height.div_ceil(4) * length,
+            None => width * height * RGBA_PIXEL_LENGTH,
+        }
+    }
+}
+
+impl RgbaImage {
+    /// Decodes the main surface of a texture, ignoring mipmaps and other layers
+    pub fn from_dds(dds: &Dds) -> Result<Self> {
+        let format = PixelFormat::from_dds(dds)?;
+        Self::decode(format, dds.get_width(), dds.get_height(), &dds.data)
+    }
+
+    /// Decodes a surface of the given format and dimensions
+    ///
+    /// Bytes found after the surface are ignored.
+    pub fn decode(format: PixelFormat, width: u32, height: u32, data: &[u8]) -> Result<Self> {
+        let length = format.data_length(width, height);
+        let Some(data) = data.get(..length) else {
+            return Err(AssetErrors::UnexpectedEof {
+                offset: data.len() as u64,
+            });
+        };
+
+        let pixels = match format {
+            PixelFormat::Bgra => bgra_to_rgba(data),
+            PixelFormat::Rgba => data.to_vec(),
+            PixelFormat::Bgrx | PixelFormat::Rgbx => {
+                let mut pixels = match format {
+                    PixelFormat::Bgrx => bgra_to_rgba(data),
+                    _ => data.to_vec(),
+                };
+                pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 0xFF);
+                pixels
+            }
+            _ => Self::decode_blocks(format, width as usize, height as usize, data),
+        };
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decodes a block compressed surface, clipping blocks at the right and bottom edges
+    ///
+    /// Premultiplied colors of DXT2 and DXT4 surfaces are divided by their alpha,
+    /// texels without any alpha being kept as stored.
+    fn decode_blocks(format: PixelFormat, width: usize, height: usize, data: &[u8]) -> Vec<u8> {
+        let block_length = format.block_length().unwrap_or(16);
+        let columns = width.div_ceil(4);
+        let mut pixels = vec![0; width * height * RGBA_PIXEL_LENGTH];
+
+        for (i, block) in data.chunks_exact(block_length).enumerate() {
+            let mut texels = match format {
+                PixelFormat::Bc1 => color_block(block, false),
+                PixelFormat::Bc2 | PixelFormat::Dxt2 => {
+                    let mut texels = color_block(&block[8..], true);
+                    let alpha = u64::from_le_bytes(block[..8].try_into().unwrap());
+                    for (j, texel) in texels.iter_mut().enumerate() {
+                        texel[3] = ((alpha >> (4 * j)) & 0xF) as u8 * 17;
+                    }
+                    texels
+                }
+                PixelFormat::Bc3 | PixelFormat::Dxt4 => {
+                    let mut texels = color_block(&block[8..], true);
+                    let alpha = channel_block(&block[..8]);
+                    for (texel, value) in texels.iter_mut().zip(alpha) {
+                        texel[3] = value;
+                    }
+                    texels
+                }
+                PixelFormat::Bc4 => channel_block(block).map(|value| [value, value, value, 0xFF]),
+                _ => {
+                    let red = channel_block(&block[..8]);
+                    let green = channel_block(&block[8..]);
+                    std::array::from_fn(|j| [red[j], green[j], 0, 0xFF])
+                }
+            };
+
+            if matches!(format, PixelFormat::Dxt2 | PixelFormat::Dxt4) {
+                texels.iter_mut().for_each(unpremultiply);
+            }
+
+            let (bx, by) = ((i % columns) * 4, (i / columns) * 4);
+            for (j, texel) in texels.iter().enumerate() {
+                let (x, y) = (bx + j % 4, by + j / 4);
+                if x < width && y < height {
+                    let pos = (y * width + x) * RGBA_PIXEL_LENGTH;
+                    pixels[pos..pos + RGBA_PIXEL_LENGTH].copy_from_slice(texel);
+                }
+            }
+        }
+
+        pixels
+    }
+
+    /// Writes the image as a PNG file
+    pub fn write_png<T: Write>(&self, writer: T) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Expands a R5G6B5 color to 8 bits per channel
+fn rgb565(color: u16) -> [u8; 4] {
+    let r = ((color >> 11) & 0x1F) as u8;
+    let g = ((color >> 5) & 0x3F) as u8;
+    let b = (color & 0x1F) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+        0xFF,
+    ]
+}
+
+/// Divides the color channels of a premultiplied texel by its alpha
+fn unpremultiply(texel: &mut [u8; 4]) {
+    let alpha = texel[3] as u16;
+    if alpha == 0 {
+        return;
+    }
+
+    for channel in &mut texel[..3] {
+        *channel = ((*channel as u16 * 0xFF + alpha / 2) / alpha).min(0xFF) as u8;
+    }
+}
+
+/// Decodes the color part of a block, as used by BC1, BC2 and BC3
+///
+/// When the first color is not greater than the second one, BC1 blocks
+/// use three colors and a transparent black. BC2 and BC3 blocks always
+/// use four colors.
+fn color_block(block: &[u8], four_colors: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    let (p0, p1) = (rgb565(c0), rgb565(c1));
+    let mix = |w0: u16, w1: u16| -> [u8; 4] {
+        std::array::from_fn(|i| ((p0[i] as u16 * w0 + p1[i] as u16 * w1) / (w0 + w1)) as u8)
+    };
+
+    let palette = if four_colors || c0 > c1 {
+        [p0, p1, mix(2, 1), mix(1, 2)]
+    } else {
+        [p0, p1, mix(1, 1), [0; 4]]
+    };
+
+    std::array::from_fn(|i| palette[((indices >> (2 * i)) & 0x3) as usize])
+}
+
+/// Decodes a block of interpolated values, as used by BC3 alpha, BC4 and BC5
+fn channel_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u16, block[1] as u16);
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u16) * a0 + i as u16 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u16) * a0 + i as u16 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 0xFF;
+    }
+
+    std::array::from_fn(|i| palette[((indices >> (3 * i)) & 0x7) as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PixelFormat, RgbaImage};
+    use crate::errors::AssetErrors;
+    use ddsfile::{D3DFormat, Dds, NewD3dParams};
+
+    const RED: [u8; 4] = [0xFF, 0, 0, 0xFF];
+    const BLUE: [u8; 4] = [0, 0, 0xFF, 0xFF];
+
+    fn pixel(image: &RgbaImage, x: usize, y: usize) -> [u8; 4] {
+        let pos = (y * image.width as usize + x) * 4;
+        image.pixels[pos..pos + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_decode_bc1() {
+        // red and blue, first row using every index, second row transparent
+        let mut block = vec![0x00, 0xF8, 0x1F, 0x00];
+        block.extend_from_slice(&[0b11_10_01_00, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc1, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), RED);
+        assert_eq!(pixel(&image, 1, 0), BLUE);
+        assert_eq!(pixel(&image, 2, 0), [0xAA, 0, 0x55, 0xFF]);
+        assert_eq!(pixel(&image, 3, 0), [0x55, 0, 0xAA, 0xFF]);
+
+        // swapped colors switch to three colors and transparent black
+        let mut block = vec![0x1F, 0x00, 0x00, 0xF8];
+        block.extend_from_slice(&[0b11_10_01_00, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc1, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 2, 0), [0x7F, 0, 0x7F, 0xFF]);
+        assert_eq!(pixel(&image, 3, 0), [0; 4]);
+    }
+
+    #[test]
+    fn test_decode_bc2() {
+        // alpha of 0, 15 and 8, colors in swapped order still using four colors
+        let mut block = vec![0xF0, 0x08, 0, 0, 0, 0, 0, 0];
+        block.extend_from_slice(&[0x1F, 0x00, 0x00, 0xF8, 0b00_00_00_11, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc2, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [0xAA, 0, 0x55, 0]);
+        assert_eq!(pixel(&image, 1, 0), BLUE);
+        assert_eq!(pixel(&image, 2, 0), [0, 0, 0xFF, 0x88]);
+        assert_eq!(pixel(&image, 3, 0), [0, 0, 0xFF, 0]);
+    }
+
+    #[test]
+    fn test_decode_premultiplied() {
+        // a dark blue with an alpha of 8, stored premultiplied
+        let mut block = vec![0x88, 0, 0, 0, 0, 0, 0, 0];
+        block.extend_from_slice(&[0x10, 0x00, 0x10, 0x00, 0, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc2, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [0, 0, 0x84, 0x88]);
+        let image = RgbaImage::decode(PixelFormat::Dxt2, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [0, 0, 0xF8, 0x88]);
+        assert_eq!(pixel(&image, 2, 0), [0, 0, 0x84, 0]);
+
+        let dds = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::DXT4,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        assert_eq!(PixelFormat::from_dds(&dds).unwrap(), PixelFormat::Dxt4);
+    }
+
+    #[test]
+    fn test_decode_bc3() {
+        // alpha from 255 to 0, first texels using indices 0, 1 and 2
+        let mut block = vec![0xFF, 0x00, 0b10_001_000, 0, 0, 0, 0, 0];
+        block.extend_from_slice(&[0x00, 0xF8, 0x1F, 0x00, 0, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc3, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), RED);
+        assert_eq!(pixel(&image, 1, 0)[3], 0);
+        assert_eq!(pixel(&image, 2, 0)[3], 218);
+        assert_eq!(pixel(&image, 3, 3)[3], 0xFF);
+    }
+
+    #[test]
+    fn test_decode_bc4() {
+        // values from 255 to 0, first texels using indices 0, 1 and 2
+        let block = [0xFF, 0x00, 0b10_001_000, 0, 0, 0, 0, 0];
+        let image = RgbaImage::decode(PixelFormat::Bc4, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [0xFF; 4]);
+        assert_eq!(pixel(&image, 1, 0), [0, 0, 0, 0xFF]);
+        assert_eq!(pixel(&image, 2, 0), [218, 218, 218, 0xFF]);
+    }
+
+    #[test]
+    fn test_decode_bc5() {
+        // green goes from 0 to 255 with six values, using indices 7, 6 and 2
+        let mut block = vec![0xFF, 0x00, 0b10_001_000, 0, 0, 0, 0, 0];
+        block.extend_from_slice(&[0x00, 0xFF, 0b10_110_111, 0, 0, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc5, 4, 4, &block).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [0xFF, 0xFF, 0, 0xFF]);
+        assert_eq!(pixel(&image, 1, 0), [0, 0, 0, 0xFF]);
+        assert_eq!(pixel(&image, 2, 0), [218, 51, 0, 0xFF]);
+    }
+
+    #[test]
+    fn test_decode_unused_alpha() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 0];
+        let image = RgbaImage::decode(PixelFormat::Bgrx, 2, 1, &data).unwrap();
+        assert_eq!(image.pixels, vec![3, 2, 1, 0xFF, 7, 6, 5, 0xFF]);
+        let image = RgbaImage::decode(PixelFormat::Rgbx, 2, 1, &data).unwrap();
+        assert_eq!(image.pixels, vec![1, 2, 3, 0xFF, 5, 6, 7, 0xFF]);
+    }
+
+    #[test]
+    fn test_decode_clipped() {
+        // a 5x3 surface uses two blocks, clipped to the image size
+        let mut data = vec![0x00, 0xF8, 0x00, 0xF8, 0, 0, 0, 0];
+        data.extend_from_slice(&[0x1F, 0x00, 0x1F, 0x00, 0, 0, 0, 0]);
+        let image = RgbaImage::decode(PixelFormat::Bc1, 5, 3, &data).unwrap();
+        assert_eq!(image.pixels.len(), 5 * 3 * 4);
+        assert_eq!(pixel(&image, 3, 2), RED);
+        assert_eq!(pixel(&image, 4, 2), BLUE);
+
+        match RgbaImage::decode(PixelFormat::Bc1, 5, 5, &data) {
+            Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, 16),
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_dds_to_png() {
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 2,
+            width: 1,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: None,
+            caps2: None,
+        })
+        .unwrap();
+        dds.data = vec![0, 0, 0xFF, 0xFF, 0xFF, 0, 0, 0x80];
+
+        let image = RgbaImage::from_dds(&dds).unwrap();
+        assert_eq!(image.pixels, vec![0xFF, 0, 0, 0xFF, 0, 0, 0xFF, 0x80]);
+
+        let mut bytes = vec![];
+        image.write_png(&mut bytes).unwrap();
+
+        let decoder = png::Decoder::new(bytes.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (1, 2));
+        assert_eq!(pixels, image.pixels);
+    }
+}
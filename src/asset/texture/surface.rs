@@ -0,0 +1,234 @@
+use crate::errors::{AssetErrors, Result};
+use ddsfile::{Caps2, D3DFormat, DataFormat, Dds, DxgiFormat, MiscFlag};
+use std::ops::Range;
+
+#[cfg(feature = "image")]
+use super::rgba::{PixelFormat, RgbaImage};
+
+/// Faces of a cubemap, in the order they are stored
+const CUBEMAP_FACES: [(Caps2, CubemapFace); 6] = [
+    (Caps2::CUBEMAP_POSITIVEX, CubemapFace::PositiveX),
+    (Caps2::CUBEMAP_NEGATIVEX, CubemapFace::NegativeX),
+    (Caps2::CUBEMAP_POSITIVEY, CubemapFace::PositiveY),
+    (Caps2::CUBEMAP_NEGATIVEY, CubemapFace::NegativeY),
+    (Caps2::CUBEMAP_POSITIVEZ, CubemapFace::PositiveZ),
+    (Caps2::CUBEMAP_NEGATIVEZ, CubemapFace::NegativeZ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubemapFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+/// The format of a surface, as declared in the DDS header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceFormat {
+    Dxgi(DxgiFormat),
+    D3d(D3DFormat),
+    /// An uncompressed format not known by name, given by its pixel size
+    Uncompressed {
+        bits_per_pixel: u32,
+    },
+}
+
+/// A surface stored in a texture: a mip level of an array layer or cubemap face
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureSurface {
+    /// The array layer holding the surface, zero unless the texture is an array
+    pub layer: u32,
+    /// The cubemap face holding the surface, if the texture is a cubemap
+    pub face: Option<CubemapFace>,
+    /// The mip level, zero being the largest one
+    pub level: u32,
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub format: SurfaceFormat,
+    /// Byte range of the surface within the data of the DDS file
+    pub range: Range<usize>,
+}
+
+impl SurfaceFormat {
+    /// Retrieves the format of a DDS file
+    pub fn from_dds(dds: &Dds) -> Result<Self> {
+        if let Some(format) = dds.get_dxgi_format() {
+            return Ok(Self::Dxgi(format));
+        }
+
+        if let Some(format) = dds.get_d3d_format() {
+            return Ok(Self::D3d(format));
+        }
+
+        match dds.header.spf.rgb_bit_count {
+            Some(bits_per_pixel) if bits_per_pixel > 0 => Ok(Self::Uncompressed { bits_per_pixel }),
+            _ => Err(AssetErrors::UnsupportedTextureFormat(format!(
+                "{:?}",
+                dds.header.spf
+            ))),
+        }
+    }
+
+    /// Returns the bytes used by a surface of the given dimensions
+    pub fn surface_length(&self, width: u32, height: u32, depth: u32) -> Result<usize> {
+        let (pitch, pitch_height) = match self {
+            Self::Dxgi(format) => (format.get_pitch(width), format.get_pitch_height()),
+            Self::D3d(format) => (format.get_pitch(width), format.get_pitch_height()),
+            Self::Uncompressed { bits_per_pixel } => {
+                (Some((bits_per_pixel * width).div_ceil(8)), 1)
+            }
+        };
+
+        let Some(pitch) = pitch else {
+            return Err(AssetErrors::UnsupportedTextureFormat(format!("{:?}", self)));
+        };
+
+        let rows = height.div_ceil(pitch_height) as usize;
+        Ok(pitch as usize * rows * depth as usize)
+    }
+}
+
+impl TextureSurface {
+    /// Lists every surface of a texture, in the order they are stored
+    ///
+    /// Each array layer, or cubemap face, holds a full chain of mip levels.
+    /// The given dimensions are the ones of the largest mip level, as
+    /// found in the texture header.
+    pub fn list(dds: &Dds, width: u32, height: u32) -> Result<Vec<Self>> {
+        let format = SurfaceFormat::from_dds(dds)?;
+        let levels = dds.get_num_mipmap_levels().max(1);
+        let depth = dds.get_depth().max(1);
+
+        let (layers, faces): (u32, Vec<Option<CubemapFace>>) = match &dds.header10 {
+            Some(header10) if header10.misc_flag.contains(MiscFlag::TEXTURECUBE) => (
+                header10.array_size.max(1),
+                CUBEMAP_FACES.iter().map(|(_, face)| Some(*face)).collect(),
+            ),
+            Some(header10) => (header10.array_size.max(1), vec![None]),
+            None if dds.header.caps2.contains(Caps2::CUBEMAP) => (
+                1,
+                CUBEMAP_FACES
+                    .iter()
+                    .filter(|(flag, _)| dds.header.caps2.contains(*flag))
+                    .map(|(_, face)| Some(*face))
+                    .collect(),
+            ),
+            None => (1, vec![None]),
+        };
+
+        let mut surfaces = vec![];
+        let mut pos = 0;
+        for layer in 0..layers {
+            for face in &faces {
+                for level in 0..levels {
+                    let width = (width >> level).max(1);
+                    let height = (height >> level).max(1);
+                    let depth = (depth >> level).max(1);
+
+                    let end = pos + format.surface_length(width, height, depth)?;
+                    if end > dds.data.len() {
+                        return Err(AssetErrors::UnexpectedEof {
+                            offset: dds.data.len() as u64,
+                        });
+                    }
+
+                    surfaces.push(Self {
+                        layer,
+                        face: *face,
+                        level,
+                        width,
+                        height,
+                        depth,
+                        format,
+                        range: pos..end,
+                    });
+                    pos = end;
+                }
+            }
+        }
+
+        Ok(surfaces)
+    }
+
+    /// Returns the bytes of the surface, taken from the DDS file it was listed from
+    pub fn data<'a>(&self, dds: &'a Dds) -> &'a [u8] {
+        &dds.data[self.range.clone()]
+    }
+
+    /// Decodes the surface to RGBA pixels, taken from the DDS file it was listed from
+    #[cfg(feature = "image")]
+    pub fn decode(&self, dds: &Dds) -> Result<RgbaImage> {
+        let format = PixelFormat::from_dds(dds)?;
+        RgbaImage::decode(format, self.width, self.height, self.data(dds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CubemapFace, SurfaceFormat, TextureSurface};
+    use crate::errors::AssetErrors;
+    use ddsfile::{
+        AlphaMode, D3D10ResourceDimension, D3DFormat, Dds, DxgiFormat, NewD3dParams, NewDxgiParams,
+    };
+
+    #[test]
+    fn test_mip_levels() {
+        let mut dds = Dds::new_d3d(NewD3dParams {
+            height: 32,
+            width: 64,
+            depth: None,
+            format: D3DFormat::DXT1,
+            mipmap_levels: Some(3),
+            caps2: None,
+        })
+        .unwrap();
+        dds.data[1024] = 0xFF;
+
+        let surfaces = TextureSurface::list(&dds, 64, 32).unwrap();
+        assert_eq!(surfaces.len(), 3);
+        assert_eq!(surfaces[0].range, 0..1024);
+        assert_eq!(surfaces[1].range, 1024..1280);
+        assert_eq!(surfaces[2].range, 1280..1344);
+        assert_eq!((surfaces[2].width, surfaces[2].height), (16, 8));
+        assert_eq!(surfaces[1].data(&dds)[0], 0xFF);
+        assert!(surfaces.iter().all(|s| s.layer == 0 && s.face.is_none()));
+        assert!(matches!(surfaces[0].format, SurfaceFormat::Dxgi(_)));
+
+        // the data must hold every surface
+        dds.data.truncate(1300);
+        match TextureSurface::list(&dds, 64, 32) {
+            Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, 1300),
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_cubemap_faces() {
+        let dds = Dds::new_dxgi(NewDxgiParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: DxgiFormat::B8G8R8A8_UNorm,
+            mipmap_levels: Some(2),
+            array_layers: Some(6),
+            caps2: None,
+            is_cubemap: true,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Unknown,
+        })
+        .unwrap();
+
+        let surfaces = TextureSurface::list(&dds, 4, 4).unwrap();
+        assert_eq!(surfaces.len(), 12);
+        assert_eq!(surfaces[0].face, Some(CubemapFace::PositiveX));
+        assert_eq!(surfaces[1].range, 64..80);
+        assert_eq!((surfaces[1].width, surfaces[1].height), (2, 2));
+        assert_eq!(surfaces[11].face, Some(CubemapFace::NegativeZ));
+        assert_eq!(surfaces[11].level, 1);
+        assert_eq!(surfaces[11].range.end, dds.data.len());
+    }
+}
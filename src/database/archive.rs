@@ -0,0 +1,581 @@
+use super::records::RecordInfo;
+use super::NODE_HEADER_LENGTH;
+use crate::asset::texture::TextureHeader;
+use crate::asset::ASSET_NODE_START;
+use crate::errors::{AssetErrors, Result};
+use crate::reader::OffsetReader;
+use crate::resource::{graph::NodeGraph, item::ResourceIndexItem, node::ResourceIndexNode};
+use crate::{AssetBookmark, AssetContent, AssetData, AssetHeader, AssetType, ResourceIndex};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// A struct holding an index file along with its database file
+pub struct Archive {
+    pub index: ResourceIndex,
+    name: String,
+    index_path: PathBuf,
+    database: File,
+}
+
+impl Archive {
+    /// Opens an index/database pair for reading
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(index_path: P, database_path: Q) -> Result<Self> {
+        let index = ResourceIndex::read(File::open(&index_path)?)?;
+        let database = File::open(database_path)?;
+
+        Ok(Self {
+            index,
+            name: Self::file_stem(index_path.as_ref()),
+            index_path: index_path.as_ref().to_path_buf(),
+            database,
+        })
+    }
+
+    /// Opens an index/database pair for reading and writing
+    pub fn open_writable<P: AsRef<Path>, Q: AsRef<Path>>(
+        index_path: P,
+        database_path: Q,
+    ) -> Result<Self> {
+        let index = ResourceIndex::read(File::open(&index_path)?)?;
+        let database = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(database_path)?;
+
+        Ok(Self {
+            index,
+            name: Self::file_stem(index_path.as_ref()),
+            index_path: index_path.as_ref().to_path_buf(),
+            database,
+        })
+    }
+
+    /// Returns the name shared by both files, such as `data2`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the path to the index file
+    pub fn index_path(&self) -> &Path {
+        &self.index_path
+    }
+
+    fn file_stem(path: &Path) -> String {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Reads the asset pointed by the given bookmark
+    pub fn read(&self, bookmark: &AssetBookmark) -> Result<AssetData> {
+        AssetData::read(&self.database, bookmark)
+    }
+
+    /// Reads the header of the asset pointed by the given bookmark, without its content
+    pub fn read_header(&self, bookmark: &AssetBookmark) -> Result<AssetHeader> {
+        AssetData::read_header(&self.database, bookmark)
+    }
+
+    /// Walks the node chain of the index, checking it against the database file
+    ///
+    /// The record held by each node of the chain is read to report the
+    /// bytes left between its end and the following node as slack.
+    pub fn check(&self) -> Result<NodeGraph> {
+        let database_length = self.database.metadata()?.len();
+        let mut graph = self.index.node_graph(database_length);
+
+        let mut reader = OffsetReader::new(BufReader::new(&self.database));
+        for pos in graph.chain.clone() {
+            let node_end = self.index.nodes[pos].node_end as u64;
+            if let Some(record_end) = Self::record_end(&mut reader, node_end)? {
+                graph.add_slack(&self.index.nodes, pos, record_end);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Returns the end of the record found at the given offset
+    ///
+    /// A block not starting with a `PAIR` string holds no record, so it ends
+    /// at the given offset. Records that cannot be read are not reported.
+    fn record_end<T: Read + Seek>(
+        reader: &mut OffsetReader<T>,
+        offset: u64,
+    ) -> Result<Option<u64>> {
+        reader.seek(SeekFrom::Start(offset))?;
+        match reader.read_array::<4>() {
+            Ok(magic) if magic == ASSET_NODE_START.as_bytes() => {}
+            Ok(_) | Err(AssetErrors::UnexpectedEof { .. }) => return Ok(Some(offset)),
+            Err(err) => return Err(err),
+        }
+
+        reader.seek(SeekFrom::Start(offset))?;
+        Ok(RecordInfo::read(reader).ok().map(|record| record.end))
+    }
+
+    /// Replaces the content of an asset, updating both the database and the index
+    ///
+    /// The block is first copied to the end of the database, holding the new
+    /// record, and its old space is added to the preceding block, keeping the
+    /// node chain valid. When the new record fits in the old block, it is
+    /// then moved back there and the copy is truncated. Otherwise, the old
+    /// record is left in place: `Archive::check` reports that space as
+    /// slack, and `records::orphans` finds the old record.
+    /// The content must match the type of the asset being replaced, whose
+    /// current content does not need to be readable. A texture is written
+    /// with the header it is given, use `replace_keeping_header` to keep the
+    /// one already stored.
+    ///
+    /// Existing records are only overwritten once an index no longer using
+    /// them is in place. If writing the first index fails, the copy is
+    /// truncated and both files are left as they were. If moving the record
+    /// back fails, the error is returned with the asset left at the end of
+    /// the database, as described by the index file.
+    pub fn replace(&mut self, resource_id: u32, content: AssetContent) -> Result<AssetBookmark> {
+        let bookmark = self
+            .index
+            .get_by_resource_id(resource_id)
+            .ok_or(AssetErrors::UnknownResourceError(resource_id))?;
+        if !content.is_compatible(&bookmark.asset_type) {
+            return Err(AssetErrors::ContentMismatch(bookmark.asset_type));
+        }
+
+        let (node_pos, item_pos) = self
+            .index
+            .locate(&bookmark)
+            .ok_or(AssetErrors::UnknownResourceError(resource_id))?;
+
+        let mut asset = AssetData::read_record(&self.database, &bookmark)?;
+        let payload = content.to_bytes()?;
+        asset.content = content;
+
+        let mut record = vec![];
+        asset.write(&mut record, &payload)?;
+        let size = u32::try_from(payload.len())?;
+
+        // keep the original block header
+        let mut header = [0; NODE_HEADER_LENGTH];
+        self.database
+            .seek(SeekFrom::Start(bookmark.node_start as u64))?;
+        self.database.read_exact(&mut header)?;
+
+        let database_length = self.database.seek(SeekFrom::End(0))?;
+        let nodes = self.index.nodes.clone();
+        let items = self.index.items.clone();
+
+        // the copy is written after every record, so none of them is touched yet
+        let copied = self.append_block(&header, &record).and_then(|_| {
+            self.unlink(node_pos, database_length as usize)?;
+
+            let node_start = u32::try_from(database_length)?;
+            let node_end = node_start + NODE_HEADER_LENGTH as u32;
+            let node = &mut self.index.nodes[node_pos];
+            node.node_start = node_start;
+            node.node_end = node_end;
+            node.node_next = node_end + u32::try_from(record.len())?;
+
+            let item = &mut self.index.items[item_pos];
+            item.start = node_start;
+            item.size = size;
+
+            self.index.refresh()?;
+            self.write_index()
+        });
+
+        if let Err(err) = copied {
+            self.restore_index(nodes, items)?;
+            self.database.set_len(database_length)?;
+            return Err(err);
+        }
+
+        if bookmark.node_end + record.len() <= bookmark.node_next {
+            // the index now points to the copy, so the old record can be overwritten
+            let relocated = (self.index.nodes.clone(), self.index.items.clone());
+            let moved = self
+                .write_record(bookmark.node_end as u64, &record)
+                .and_then(|_| {
+                    self.index.nodes = nodes;
+                    self.index.items = items;
+                    self.index.items[item_pos].size = size;
+                    self.index.refresh()?;
+                    self.write_index()
+                });
+
+            if let Err(err) = moved {
+                self.restore_index(relocated.0, relocated.1)?;
+                return Err(err);
+            }
+            self.database.set_len(database_length)?;
+        }
+
+        self.index
+            .get_by_resource_id(resource_id)
+            .ok_or(AssetErrors::UnknownResourceError(resource_id))
+    }
+
+    /// Replaces the content of an asset as done by `replace`, keeping the texture header already stored
+    ///
+    /// The header given along with a texture is ignored, so the engine
+    /// settings of the stored texture are not lost. Only the header of the
+    /// stored texture is read, its DDS file being left untouched. Other
+    /// contents are replaced as they are given.
+    pub fn replace_keeping_header(
+        &mut self,
+        resource_id: u32,
+        content: AssetContent,
+    ) -> Result<AssetBookmark> {
+        let mut content = content;
+        if let AssetContent::Texture { header, .. } = &mut content {
+            let bookmark = self
+                .index
+                .get_by_resource_id(resource_id)
+                .ok_or(AssetErrors::UnknownResourceError(resource_id))?;
+            **header = self.read_texture_header(&bookmark)?;
+        }
+
+        self.replace(resource_id, content)
+    }
+
+    /// Reads the values found before the DDS file of the texture pointed by the given bookmark
+    pub fn read_texture_header(&self, bookmark: &AssetBookmark) -> Result<TextureHeader> {
+        if bookmark.asset_type != AssetType::Texture {
+            return Err(AssetErrors::ContentMismatch(bookmark.asset_type.clone()));
+        }
+
+        let header = self.read_header(bookmark)?;
+        let mut database = &self.database;
+        database.seek(SeekFrom::Start(header.payload.start))?;
+        AssetContent::read_texture_header(database)
+    }
+
+    /// Appends a block to the end of the database, making sure it is stored
+    fn append_block(&mut self, header: &[u8], record: &[u8]) -> Result<()> {
+        self.database.seek(SeekFrom::End(0))?;
+        self.database.write_all(header)?;
+        self.database.write_all(record)?;
+        self.database.sync_data()?;
+        Ok(())
+    }
+
+    /// Writes a record at the given offset of the database, making sure it is stored
+    fn write_record(&mut self, offset: u64, record: &[u8]) -> Result<()> {
+        self.database.seek(SeekFrom::Start(offset))?;
+        self.database.write_all(record)?;
+        self.database.sync_data()?;
+        Ok(())
+    }
+
+    /// Brings back the nodes and items of the index, after a failed write
+    fn restore_index(
+        &mut self,
+        nodes: Vec<ResourceIndexNode>,
+        items: Vec<ResourceIndexItem>,
+    ) -> Result<()> {
+        self.index.nodes = nodes;
+        self.index.items = items;
+        self.index.refresh()
+    }
+
+    /// Writes the index to a temporary file, then moves it over the index file
+    ///
+    /// The index file is left untouched if writing fails.
+    fn write_index(&self) -> Result<()> {
+        let mut temp_path = self.index_path.clone().into_os_string();
+        temp_path.push(".tmp");
+
+        let mut output = BufWriter::new(File::create(&temp_path)?);
+        self.index.write(&mut output)?;
+        output
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+
+        std::fs::rename(&temp_path, &self.index_path)?;
+        Ok(())
+    }
+
+    /// Removes a node from the chain so it can be appended after the last one
+    ///
+    /// The block of the removed node is added to the preceding one.
+    fn unlink(&mut self, node_pos: usize, database_length: usize) -> Result<()> {
+        let nodes = &mut self.index.nodes;
+        let database_length = u32::try_from(database_length)?;
+        let ResourceIndexNode {
+            node_start,
+            node_next,
+            node_previous,
+            ..
+        } = nodes[node_pos].clone();
+
+        // a node already last keeps its predecessor
+        let last = nodes
+            .iter()
+            .find(|node| node.node_next == database_length && node.node_start != node_start)
+            .map(|node| node.node_start);
+
+        if let Some(previous) = nodes
+            .iter_mut()
+            .find(|node| node.node_start == node_previous && node.node_next == node_start)
+        {
+            previous.node_next = node_next;
+        }
+
+        if let Some(next) = nodes.iter_mut().find(|node| node.node_start == node_next) {
+            next.node_previous = node_previous;
+        }
+
+        if let Some(last) = last {
+            nodes[node_pos].node_previous = last;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Archive, NODE_HEADER_LENGTH};
+    use crate::asset::texture::TextureHeader;
+    use crate::database::records::orphans;
+    use crate::database::tests::sound;
+    use crate::errors::AssetErrors;
+    use crate::resource::graph::{Region, RegionKind};
+    use crate::{AssetContent, AssetData, AssetType, DatabaseWriter};
+    use ddsfile::{D3DFormat, Dds, NewD3dParams};
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    fn create_archive(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir();
+        let index_path = dir.join(format!("regnumassets_{}.idx", name));
+        let database_path = dir.join(format!("regnumassets_{}.sdb", name));
+
+        let mut database = DatabaseWriter::new(File::create(&database_path).unwrap()).unwrap();
+        database.add(&sound(1, &[1; 64])).unwrap();
+        database.add(&sound(2, &[2; 64])).unwrap();
+        database.add(&sound(3, &[3; 64])).unwrap();
+        let index = database.finish().unwrap();
+        index.write(File::create(&index_path).unwrap()).unwrap();
+
+        (index_path, database_path)
+    }
+
+    fn sound_bytes(archive: &Archive, resource_id: u32) -> Vec<u8> {
+        let bookmark = archive.index.get_by_resource_id(resource_id).unwrap();
+        match archive.read(&bookmark).unwrap().content {
+            AssetContent::Sound { bytes, .. } => bytes,
+            _ => panic!("expected a sound"),
+        }
+    }
+
+    #[test]
+    fn test_replace_in_place() {
+        let (index_path, database_path) = create_archive("replace_in_place");
+        let length = std::fs::metadata(&database_path).unwrap().len();
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let previous = archive.index.get_by_resource_id(2).unwrap();
+        let bookmark = archive.replace(2, sound(2, &[9; 16]).content).unwrap();
+        assert_eq!(bookmark.node_start, previous.node_start);
+        assert_eq!(bookmark.size, previous.size - 48);
+
+        let archive = Archive::open(&index_path, &database_path).unwrap();
+        assert_eq!(std::fs::metadata(&database_path).unwrap().len(), length);
+        assert_eq!(sound_bytes(&archive, 1), vec![1; 64]);
+        assert_eq!(sound_bytes(&archive, 2), vec![9; 16]);
+        assert_eq!(sound_bytes(&archive, 3), vec![3; 64]);
+    }
+
+    #[test]
+    fn test_replace_relocated() {
+        let (index_path, database_path) = create_archive("replace_relocated");
+        let length = std::fs::metadata(&database_path).unwrap().len() as usize;
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let previous = archive.index.get_by_resource_id(1).unwrap();
+        let bookmark = archive.replace(1, sound(1, &[7; 256]).content).unwrap();
+        assert_eq!(bookmark.node_start, length);
+
+        let archive = Archive::open(&index_path, &database_path).unwrap();
+        assert_eq!(sound_bytes(&archive, 1), vec![7; 256]);
+        assert_eq!(sound_bytes(&archive, 2), vec![2; 64]);
+        assert_eq!(sound_bytes(&archive, 3), vec![3; 64]);
+
+        // the old block is added to the root block, and reported as slack
+        let graph = archive.check().unwrap();
+        assert!(graph.is_valid());
+        assert_eq!(
+            archive.index.nodes[0].node_next as usize,
+            previous.node_next
+        );
+        assert_eq!(
+            graph.regions,
+            vec![Region {
+                start: NODE_HEADER_LENGTH as u64,
+                end: previous.node_next as u64,
+                kind: RegionKind::Slack { node: 0 },
+            }]
+        );
+
+        // the old record can still be found
+        let data = std::fs::read(&database_path).unwrap();
+        let found = orphans(&data, &archive.index);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].offset as usize, previous.node_end);
+    }
+
+    #[test]
+    fn test_replace_last() {
+        let (index_path, database_path) = create_archive("replace_last");
+        let length = std::fs::metadata(&database_path).unwrap().len() as usize;
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let previous = archive.index.get_by_resource_id(3).unwrap();
+        let bookmark = archive.replace(3, sound(3, &[7; 256]).content).unwrap();
+
+        // the last block is relocated too, right after itself
+        let record_length = previous.node_next - previous.node_end;
+        assert_eq!(bookmark.node_start, length);
+        assert_eq!(bookmark.node_previous, previous.node_previous);
+        assert_eq!(
+            bookmark.node_next,
+            length + NODE_HEADER_LENGTH + record_length + 192
+        );
+
+        let archive = Archive::open(&index_path, &database_path).unwrap();
+        assert_eq!(sound_bytes(&archive, 3), vec![7; 256]);
+        assert!(archive.check().unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_replace_unreadable() {
+        let dir = std::env::temp_dir();
+        let index_path = dir.join("regnumassets_replace_unreadable.idx");
+        let database_path = dir.join("regnumassets_replace_unreadable.sdb");
+
+        let mut database = DatabaseWriter::new(File::create(&database_path).unwrap()).unwrap();
+        database.add_payload(&sound(1, &[]), &[0xFF; 32]).unwrap();
+        let index = database.finish().unwrap();
+        index.write(File::create(&index_path).unwrap()).unwrap();
+
+        // the current content is not parsed
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let bookmark = archive.index.get_by_resource_id(1).unwrap();
+        assert!(archive.read(&bookmark).is_err());
+        archive.replace(1, sound(1, &[9; 16]).content).unwrap();
+        assert_eq!(sound_bytes(&archive, 1), vec![9; 16]);
+    }
+
+    #[test]
+    fn test_replace_index_failure() {
+        let (index_path, database_path) = create_archive("replace_index_failure");
+        let index = std::fs::read(&index_path).unwrap();
+        let database = std::fs::read(&database_path).unwrap();
+
+        // the temporary index cannot be created over a directory
+        let mut temp_path = index_path.clone().into_os_string();
+        temp_path.push(".tmp");
+        std::fs::create_dir_all(&temp_path).unwrap();
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let previous = archive.index.get_by_resource_id(2).unwrap();
+        assert!(archive.replace(2, sound(2, &[9; 256]).content).is_err());
+        std::fs::remove_dir(&temp_path).unwrap();
+
+        // both files are left as they were
+        assert_eq!(std::fs::read(&index_path).unwrap(), index);
+        assert_eq!(std::fs::read(&database_path).unwrap(), database);
+        let bookmark = archive.index.get_by_resource_id(2).unwrap();
+        assert_eq!(bookmark.node_start, previous.node_start);
+        assert_eq!(sound_bytes(&archive, 2), vec![2; 64]);
+    }
+
+    #[test]
+    fn test_replace_texture_header() {
+        let dir = std::env::temp_dir();
+        let index_path = dir.join("regnumassets_replace_texture_header.idx");
+        let database_path = dir.join("regnumassets_replace_texture_header.sdb");
+
+        let texture = |header: TextureHeader| AssetContent::Texture {
+            width: 4,
+            height: 4,
+            dds: Dds::new_d3d(NewD3dParams {
+                height: 4,
+                width: 4,
+                depth: None,
+                format: D3DFormat::DXT1,
+                mipmap_levels: None,
+                caps2: None,
+            })
+            .unwrap(),
+            header: Box::new(header),
+        };
+        let header = |unknown1| TextureHeader {
+            unknown1,
+            settings: [unknown1 as u8; 48],
+            ..TextureHeader::default()
+        };
+        let stored_header = |archive: &Archive| {
+            let bookmark = archive.index.get_by_resource_id(1260).unwrap();
+            match archive.read(&bookmark).unwrap().content {
+                AssetContent::Texture { header, .. } => *header,
+                _ => panic!("expected a texture"),
+            }
+        };
+
+        let asset = AssetData::new(AssetType::Texture, 1260, "grass", texture(header(7)));
+        let mut database = DatabaseWriter::new(File::create(&database_path).unwrap()).unwrap();
+        database.add(&asset).unwrap();
+        let index = database.finish().unwrap();
+        index.write(File::create(&index_path).unwrap()).unwrap();
+
+        // the stored header is kept, ignoring the given one
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let bookmark = archive.index.get_by_resource_id(1260).unwrap();
+        assert_eq!(archive.read_texture_header(&bookmark).unwrap(), header(7));
+        archive
+            .replace_keeping_header(1260, texture(header(9)))
+            .unwrap();
+        assert_eq!(stored_header(&archive), header(7));
+
+        // the given header is written, even a zeroed one
+        archive.replace(1260, texture(header(9))).unwrap();
+        assert_eq!(stored_header(&archive), header(9));
+        archive.replace(1260, texture(header(0))).unwrap();
+        assert_eq!(stored_header(&archive), TextureHeader::default());
+    }
+
+    #[test]
+    fn test_replace_mismatch() {
+        let (index_path, database_path) = create_archive("replace_mismatch");
+        let index = std::fs::read(&index_path).unwrap();
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        let image = AssetContent::Image {
+            bytes: vec![0xFF, 0xD8, 0xFF, 0xD9],
+        };
+        match archive.replace(1, image) {
+            Err(AssetErrors::ContentMismatch(asset_type)) => {
+                assert_eq!(asset_type, AssetType::Sound)
+            }
+            _ => panic!("expected a content mismatch error"),
+        }
+        assert_eq!(std::fs::read(&index_path).unwrap(), index);
+
+        // the index is replaced as a whole
+        archive.replace(1, sound(1, &[9; 16]).content).unwrap();
+        let mut temp_path = index_path.into_os_string();
+        temp_path.push(".tmp");
+        assert!(!PathBuf::from(temp_path).exists());
+    }
+
+    #[test]
+    fn test_replace_unknown() {
+        let (index_path, database_path) = create_archive("replace_unknown");
+
+        let mut archive = Archive::open_writable(&index_path, &database_path).unwrap();
+        assert!(archive.replace(4, sound(4, &[]).content).is_err());
+    }
+}
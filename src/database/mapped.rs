@@ -0,0 +1,258 @@
+use crate::asset::data::RecordHeader;
+use crate::asset::image::{find_last_sequence, JPEG_END};
+use crate::asset::PropertyBag;
+use crate::errors::{AssetErrors, Result};
+use crate::parser::{fail, into_asset_error, SliceErrorKind, SliceResult};
+use crate::{AssetBookmark, AssetContent, AssetType};
+use memmap2::Mmap;
+use nom::bytes::complete::take;
+use std::fs::File;
+use std::path::Path;
+
+/// A database file mapped into memory
+///
+/// Assets read from a mapped database borrow their contents from the map
+/// instead of copying them.
+pub struct MappedDatabase {
+    map: Mmap,
+}
+
+/// An asset borrowing its content from a mapped database
+pub struct MappedAsset<'a> {
+    /// The asset type
+    pub asset_type: AssetType,
+    /// A string with the form 'resource_...'
+    pub uid: String,
+    /// A string with the form 'TYPE::NAME'
+    pub resource_name: String,
+    /// The asset name
+    pub asset_name: String,
+    /// An unique identifier
+    pub resource_id: u32,
+    /// The content as stored on the database file
+    pub payload: &'a [u8],
+    /// The actual content
+    pub content: MappedContent<'a>,
+}
+
+/// An enum listing the content types found on a mapped database
+#[derive(Debug)]
+pub enum MappedContent<'a> {
+    /// A variant holding a OGG file
+    Sound {
+        filename: String,
+        size: u32,
+        bytes: &'a [u8],
+        properties: PropertyBag,
+    },
+    /// A variant holding a JPEG image
+    Image { bytes: &'a [u8] },
+    /// A variant holding content that cannot be borrowed, such as texts and textures
+    Owned(Box<AssetContent>),
+}
+
+impl MappedDatabase {
+    /// Maps the given database file into memory
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: the map is read only, the database file must not be
+        // modified while it is mapped
+        let map = unsafe { Mmap::map(&file)? };
+
+        Ok(Self { map })
+    }
+
+    /// Returns the contents of the database file
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Reads the asset pointed by the given bookmark
+    pub fn read(&self, bookmark: &AssetBookmark) -> Result<MappedAsset<'_>> {
+        MappedAsset::parse(&self.map, bookmark)
+    }
+}
+
+impl<'a> MappedAsset<'a> {
+    /// Parses the asset pointed by the given bookmark from the contents of a database file
+    pub fn parse(data: &'a [u8], bookmark: &AssetBookmark) -> Result<Self> {
+        Self::parse_record(data, bookmark)
+            .map(|(_, asset)| asset)
+            .map_err(|err| into_asset_error(err, data))
+    }
+
+    fn parse_record(data: &'a [u8], bookmark: &AssetBookmark) -> SliceResult<'a, Self> {
+        let input = data.get(bookmark.node_end..).unwrap_or_default();
+
+        let (input, header) = RecordHeader::parse(input)?;
+        if header.size != bookmark.size {
+            let err = AssetErrors::SizeMismatch {
+                expected: bookmark.size as u64,
+                found: header.size as u64,
+                offset: bookmark.node_end as u64 + header.size_offset,
+            };
+            return fail(input, SliceErrorKind::Asset(err));
+        }
+
+        let (_, payload) = take(bookmark.size)(input)?;
+
+        let (input, content) = match bookmark.asset_type {
+            AssetType::Sound | AssetType::Music => {
+                let (input, sound) = AssetContent::parse_sound_attributes(input, bookmark)?;
+                let content = match sound {
+                    Some((filename, bytes, properties)) => MappedContent::Sound {
+                        filename,
+                        size: bytes.len() as u32,
+                        bytes,
+                        properties,
+                    },
+                    None => MappedContent::Owned(Box::new(AssetContent::NotSupported)),
+                };
+                (input, content)
+            }
+            AssetType::Image => match find_last_sequence(payload, &JPEG_END) {
+                Some(pos) => (
+                    &input[payload.len()..],
+                    MappedContent::Image {
+                        bytes: &payload[..pos + 2],
+                    },
+                ),
+                None => (
+                    &input[payload.len()..],
+                    MappedContent::Owned(Box::new(AssetContent::NotSupported)),
+                ),
+            },
+            _ => {
+                let (input, content) = AssetContent::parse(input, bookmark)?;
+                (input, MappedContent::Owned(Box::new(content)))
+            }
+        };
+
+        Ok((
+            input,
+            Self {
+                asset_type: bookmark.asset_type.clone(),
+                uid: header.uid,
+                resource_name: header.resource_name,
+                asset_name: header.asset_name,
+                resource_id: header.resource_id,
+                payload,
+                content,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MappedAsset, MappedContent, MappedDatabase};
+    use crate::asset::PropertyBag;
+    use crate::asset::{TextContent, TextNode};
+    use crate::{AssetContent, AssetData, AssetType, DatabaseWriter, ResourceIndex};
+    use std::fs::File;
+
+    fn database<T: std::io::Write>(writer: T) -> ResourceIndex {
+        let mut database = DatabaseWriter::new(writer).unwrap();
+        database
+            .add(&AssetData::new(
+                AssetType::Sound,
+                50677,
+                "combat_pain_male_3",
+                AssetContent::Sound {
+                    filename: "combat_pain_male_3.ogg".into(),
+                    size: 4,
+                    bytes: b"OggS".to_vec(),
+                    properties: PropertyBag::new(),
+                },
+            ))
+            .unwrap();
+        database
+            .add(&AssetData::new(
+                AssetType::Image,
+                75879,
+                "loading",
+                AssetContent::Image {
+                    bytes: vec![0xFF, 0xD8, 0xFF, 0xD9],
+                },
+            ))
+            .unwrap();
+        database
+            .add(&AssetData::new(
+                AssetType::Text,
+                59847,
+                "eng_faction_display_name",
+                AssetContent::Text {
+                    contents: vec![TextContent {
+                        refs: vec!["1".into()],
+                        nodes: vec![TextNode::Content("Hola".into())],
+                        empty_lines: vec![],
+                    }],
+                },
+            ))
+            .unwrap();
+        database.finish().unwrap()
+    }
+
+    fn contains(data: &[u8], slice: &[u8]) -> bool {
+        data.as_ptr_range().contains(&slice.as_ptr())
+    }
+
+    #[test]
+    fn test_borrowed_contents() {
+        let mut sdb = vec![];
+        let index = database(&mut sdb);
+
+        let sound = index.get_by_resource_id(50677).unwrap();
+        let asset = MappedAsset::parse(&sdb, &sound).unwrap();
+        assert_eq!(asset.asset_name, "combat_pain_male_3");
+        assert_eq!(asset.payload.len(), sound.size as usize);
+        match asset.content {
+            MappedContent::Sound {
+                filename, bytes, ..
+            } => {
+                assert_eq!(filename, "combat_pain_male_3.ogg");
+                assert_eq!(bytes, b"OggS");
+                assert!(contains(&sdb, bytes));
+            }
+            _ => panic!("expected a sound"),
+        }
+
+        let image = index.get_by_resource_id(75879).unwrap();
+        match MappedAsset::parse(&sdb, &image).unwrap().content {
+            MappedContent::Image { bytes } => {
+                assert_eq!(bytes, [0xFF, 0xD8, 0xFF, 0xD9]);
+                assert!(contains(&sdb, bytes));
+            }
+            _ => panic!("expected an image"),
+        }
+
+        let text = index.get_by_resource_id(59847).unwrap();
+        match MappedAsset::parse(&sdb, &text).unwrap().content {
+            MappedContent::Owned(content) => match *content {
+                AssetContent::Text { contents } => assert_eq!(contents[0].refs, vec!["1"]),
+                _ => panic!("expected a text"),
+            },
+            _ => panic!("expected a text"),
+        }
+
+        // truncated data is reported instead of panicking
+        assert!(MappedAsset::parse(&sdb[..image.node_next - 6], &image).is_err());
+    }
+
+    #[test]
+    fn test_mapped_database() {
+        let path = std::env::temp_dir().join("regnumassets_mapped.sdb");
+        let index = database(File::create(&path).unwrap());
+
+        let database = MappedDatabase::open(&path).unwrap();
+        let sound = index.get_by_resource_id(50677).unwrap();
+        match database.read(&sound).unwrap().content {
+            MappedContent::Sound { bytes, .. } => {
+                assert_eq!(bytes, b"OggS");
+                assert!(contains(database.bytes(), bytes));
+            }
+            _ => panic!("expected a sound"),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+pub mod archive;
+pub mod mapped;
+pub mod records;
+pub mod writer;
+
+pub use archive::Archive;
+pub use mapped::{MappedAsset, MappedContent, MappedDatabase};
+pub use records::{Database, RecordInfo, Records};
+pub use writer::DatabaseWriter;
+
+/// Length of the block header preceding every record in a database file
+///
+/// Each index node spans one of these headers (`node_start..node_end`),
+/// the record itself starts right after it with a `PAIR` string.
+pub const NODE_HEADER_LENGTH: usize = 23;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::asset::PropertyBag;
+    use crate::{AssetContent, AssetData, AssetType};
+
+    /// Builds a sound asset holding the given bytes, named after its resource id
+    pub fn sound(resource_id: u32, bytes: &[u8]) -> AssetData {
+        AssetData::new(
+            AssetType::Sound,
+            resource_id,
+            &format!("sound_{}", resource_id),
+            AssetContent::Sound {
+                filename: format!("sound_{}.ogg", resource_id),
+                size: bytes.len() as u32,
+                bytes: bytes.to_vec(),
+                properties: PropertyBag::new(),
+            },
+        )
+    }
+}
@@ -0,0 +1,385 @@
+use super::NODE_HEADER_LENGTH;
+use crate::asset::data::{AssetHeader, RecordHeader};
+use crate::asset::{ASSET_NODE_END, ASSET_NODE_START, ASSET_TYPE_PCAUTH};
+use crate::errors::{AssetErrors, Result};
+use crate::parser::into_asset_error;
+use crate::reader::OffsetReader;
+use crate::{AssetBookmark, AssetType, ResourceIndex};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A record found in a database file, described by its header values
+#[derive(Debug, Clone)]
+pub struct RecordInfo {
+    /// Offset of the `PAIR` string starting the record
+    pub offset: u64,
+    /// Offset right after the `RIAP` string ending the record
+    pub end: u64,
+    /// The asset type
+    pub asset_type: AssetType,
+    /// A string with the form 'resource_...'
+    pub uid: String,
+    /// A string with the form 'TYPE::NAME'
+    pub resource_name: String,
+    /// The asset name
+    pub asset_name: String,
+    /// An unique identifier
+    pub resource_id: u32,
+    /// The content size
+    pub size: u32,
+}
+
+impl RecordInfo {
+    /// Parses the header of the record starting at the given offset
+    ///
+    /// The record must end with a `RIAP` string right after its content.
+    pub fn parse(data: &[u8], offset: u64) -> Result<Self> {
+        let Some(input) = usize::try_from(offset).ok().and_then(|pos| data.get(pos..)) else {
+            return Err(AssetErrors::UnexpectedEof {
+                offset: data.len() as u64,
+            });
+        };
+
+        let (rest, header) =
+            RecordHeader::parse(input).map_err(|err| into_asset_error(err, data))?;
+
+        let content = (data.len() - rest.len()) as u64;
+        let trailer = content + header.size as u64;
+        let end = trailer + ASSET_NODE_END.len() as u64;
+        match data.get(trailer as usize..end as usize) {
+            Some(found) if found == ASSET_NODE_END.as_bytes() => {}
+            Some(found) => {
+                return Err(AssetErrors::BadMagic {
+                    expected: ASSET_NODE_END.as_bytes().to_vec(),
+                    found: found.to_vec(),
+                    offset: trailer,
+                })
+            }
+            None => return Err(AssetErrors::UnexpectedEof { offset: trailer }),
+        }
+
+        Self::from_header(header, offset, end)
+    }
+
+    /// Reads the header of the record starting at the current offset, skipping its content
+    ///
+    /// The record must end with a `RIAP` string right after its content.
+    pub fn read<T: Read + Seek>(reader: &mut OffsetReader<T>) -> Result<Self> {
+        let offset = reader.offset();
+        let header = RecordHeader::read(reader)?;
+
+        reader.seek(SeekFrom::Current(header.size as i64))?;
+        reader.expect(ASSET_NODE_END.as_bytes())?;
+
+        Self::from_header(header, offset, reader.offset())
+    }
+
+    fn from_header(header: RecordHeader, offset: u64, end: u64) -> Result<Self> {
+        Ok(Self {
+            offset,
+            end,
+            asset_type: Self::asset_type(&header)?,
+            uid: header.uid,
+            resource_name: header.resource_name,
+            asset_name: header.asset_name,
+            resource_id: header.resource_id,
+            size: header.size,
+        })
+    }
+
+    /// Retrieves the asset type from the resource name of a record
+    fn asset_type(header: &RecordHeader) -> Result<AssetType> {
+        if header.uid == ASSET_TYPE_PCAUTH {
+            return Ok(AssetType::Auth);
+        }
+
+        let Some((asset_type, _)) = header.resource_name.split_once("::") else {
+            return Err(AssetErrors::InvalidResourceName(
+                header.resource_name.clone(),
+            ));
+        };
+
+        AssetType::try_from(asset_type)
+    }
+
+    /// Returns the header values of the record
+    pub fn header(&self) -> AssetHeader {
+        let payload_end = self.end - ASSET_NODE_END.len() as u64;
+
+        AssetHeader {
+            asset_type: self.asset_type.clone(),
+            uid: self.uid.clone(),
+            resource_name: self.resource_name.clone(),
+            asset_name: self.asset_name.clone(),
+            resource_id: self.resource_id,
+            payload: payload_end - self.size as u64..payload_end,
+            record: self.offset..self.end,
+        }
+    }
+
+    /// Builds a bookmark pointing to the record, so its content can be read
+    ///
+    /// The block header is assumed to precede the record, as done for
+    /// every record listed in an index.
+    pub fn bookmark(&self) -> AssetBookmark {
+        self.header().bookmark()
+    }
+}
+
+/// A database file read as a sequence of records, without using an index
+pub struct Database<R> {
+    reader: R,
+}
+
+/// An iterator over the records of a database file, in file order
+pub struct Records<'a, R> {
+    reader: &'a mut R,
+    /// Offset of the next block, or of the search for the next record
+    offset: u64,
+    length: Option<u64>,
+    /// Whether the next record is searched for after an invalid one
+    resync: bool,
+    finished: bool,
+}
+
+impl Database<BufReader<File>> {
+    /// Opens a database file for reading
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: Read + Seek> Database<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Returns an iterator over the records found in the database
+    ///
+    /// The database starts with the root block header, and each record is
+    /// preceded by its own block header. Records are read one after the
+    /// other, skipping their content by using the size found in their
+    /// header and checking the `RIAP` string found after it. When a record
+    /// cannot be read, the error is returned and the iterator searches for
+    /// the next `PAIR` string, as done by `scan`.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: &mut self.reader,
+            offset: NODE_HEADER_LENGTH as u64,
+            length: None,
+            resync: false,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> Records<'_, R> {
+    /// Reads the record found after the current offset, if any
+    ///
+    /// On error, the offset is moved past the start of the invalid record.
+    fn read_next(&mut self) -> Result<Option<AssetHeader>> {
+        let length = match self.length {
+            Some(length) => length,
+            None => *self.length.insert(self.reader.seek(SeekFrom::End(0))?),
+        };
+
+        let start = if self.resync {
+            match self.find_next()? {
+                Some(start) => start,
+                None => return Ok(None),
+            }
+        } else if self.offset >= length {
+            return Ok(None);
+        } else {
+            self.offset + NODE_HEADER_LENGTH as u64
+        };
+
+        self.reader.seek(SeekFrom::Start(start))?;
+        let mut reader = OffsetReader::with_offset(&mut *self.reader, start);
+        let record = RecordInfo::read(&mut reader);
+
+        self.resync = match &record {
+            Ok(_) => false,
+            // the record bounds are known once the RIAP string is found
+            Err(AssetErrors::UnknownAssetTypeError(_) | AssetErrors::InvalidResourceName(_)) => {
+                false
+            }
+            Err(_) => true,
+        };
+        self.offset = if self.resync {
+            start + 1
+        } else {
+            reader.offset()
+        };
+
+        record.map(|record| Some(record.header()))
+    }
+
+    /// Searches for the next `PAIR` string, starting at the current offset
+    fn find_next(&mut self) -> Result<Option<u64>> {
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = OffsetReader::with_offset(&mut *self.reader, self.offset);
+
+        let magic = ASSET_NODE_START.as_bytes();
+        let mut window = [0; 4];
+        let mut read = 0;
+        while read < magic.len() || window != magic {
+            match reader.read_u8() {
+                Ok(byte) => {
+                    window.rotate_left(1);
+                    window[3] = byte;
+                    read += 1;
+                }
+                Err(AssetErrors::UnexpectedEof { .. }) => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Some(reader.offset() - magic.len() as u64))
+    }
+}
+
+impl<R: Read + Seek> Iterator for Records<'_, R> {
+    type Item = Result<AssetHeader>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        // errors not moving the offset, like io errors, cannot be skipped
+        let offset = self.offset;
+        let record = self.read_next().transpose();
+        if record.is_none() || (matches!(record, Some(Err(_))) && self.offset == offset) {
+            self.finished = true;
+        }
+        record
+    }
+}
+
+/// Searches the contents of a database file for every record it holds
+///
+/// Any `PAIR` string followed by a valid header and a `RIAP` string is
+/// taken as a record, so records left behind by deleted or replaced
+/// assets are found as well. The search resumes after the end of each
+/// record found.
+pub fn scan(data: &[u8]) -> Vec<RecordInfo> {
+    let magic = ASSET_NODE_START.as_bytes();
+    let mut records = vec![];
+
+    let mut pos = 0;
+    while let Some(found) = data
+        .get(pos..)
+        .and_then(|rest| rest.windows(magic.len()).position(|w| w == magic))
+    {
+        let offset = pos + found;
+        match RecordInfo::parse(data, offset as u64) {
+            Ok(record) => {
+                pos = record.end as usize;
+                records.push(record);
+            }
+            Err(_) => pos = offset + 1,
+        }
+    }
+
+    records
+}
+
+/// Searches the contents of a database file for the records not referenced by an index
+pub fn orphans(data: &[u8], index: &ResourceIndex) -> Vec<RecordInfo> {
+    let referenced: HashSet<u64> = index
+        .bookmarks()
+        .iter()
+        .map(|bookmark| bookmark.node_end as u64)
+        .collect();
+
+    scan(data)
+        .into_iter()
+        .filter(|record| !referenced.contains(&record.offset))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{orphans, scan, Database};
+    use crate::database::tests::sound;
+    use crate::errors::AssetErrors;
+    use crate::{AssetContent, AssetData, AssetType, DatabaseWriter};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_scan_orphans() {
+        let mut data = vec![];
+        let mut database = DatabaseWriter::new(&mut data).unwrap();
+        database.add(&sound(1, b"PAIR")).unwrap();
+        database.add(&sound(2, &[2; 64])).unwrap();
+        let index = database.finish().unwrap();
+
+        // some garbage, followed by records missing from the index
+        let length = data.len();
+        data.extend_from_slice(b"PAIRxx");
+        data.extend_from_within(..length);
+
+        let records = scan(&data);
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].resource_id, 1);
+        assert_eq!(records[0].asset_type, AssetType::Sound);
+        assert_eq!(records[0].asset_name, "sound_1");
+        assert_eq!(records[2].offset, records[0].offset + length as u64 + 6);
+
+        let orphans = orphans(&data, &index);
+        let ids: Vec<u32> = orphans.iter().map(|record| record.resource_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+
+        // the bookmark can be used to recover the asset
+        let asset = AssetData::parse(&data, &orphans[1].bookmark()).unwrap();
+        match asset.content {
+            AssetContent::Sound { bytes, .. } => assert_eq!(bytes, vec![2; 64]),
+            _ => panic!("expected a sound"),
+        }
+    }
+
+    #[test]
+    fn test_database_records() {
+        let mut data = vec![];
+        let mut database = DatabaseWriter::new(&mut data).unwrap();
+        database.add(&sound(1, b"PAIR")).unwrap();
+        database.add(&sound(2, &[2; 64])).unwrap();
+        database.add(&sound(3, &[3; 16])).unwrap();
+        let index = database.finish().unwrap();
+
+        // records match the ones listed in the index
+        let mut database = Database::new(Cursor::new(data.clone()));
+        let records: Vec<_> = database.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        for (record, bookmark) in records.iter().zip(index.bookmarks()) {
+            assert_eq!(record.record.start as usize, bookmark.node_end);
+            assert_eq!(record.record.end as usize, bookmark.node_next);
+            assert_eq!(record.uid, bookmark.uid);
+            assert_eq!(
+                record.payload.end - record.payload.start,
+                bookmark.size as u64
+            );
+            assert_eq!(record.bookmark().node_start, bookmark.node_start);
+        }
+
+        // a wrong size is reported where the RIAP string is expected, and
+        // the following records are still found
+        let pos = records[1].record.end as usize - 4;
+        data[pos] = b'X';
+        let mut database = Database::new(Cursor::new(data));
+        let records: Vec<_> = database.records().collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].as_ref().unwrap().resource_id, 3);
+        match &records[1] {
+            Err(AssetErrors::BadMagic { offset, .. }) => assert_eq!(*offset as usize, pos),
+            _ => panic!("expected a bad magic error"),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use super::NODE_HEADER_LENGTH;
+use crate::asset::{AssetType, ASSET_TYPE_CHAR_MESH};
+use crate::errors::Result;
+use crate::resource::{item::ResourceIndexItem, node::ResourceIndexNode};
+use crate::{AssetData, ResourceIndex};
+use std::io::Write;
+
+/// A struct able to generate a database file along with its matching index
+///
+/// The first block in the database is an empty root block, followed by one
+/// block for each added asset. Block headers are not understood yet, so they
+/// are written as zeroes unless given through `add_with_block_header`.
+pub struct DatabaseWriter<T: Write> {
+    writer: T,
+    position: u32,
+    nodes: Vec<ResourceIndexNode>,
+    items: Vec<ResourceIndexItem>,
+}
+
+impl<T: Write> DatabaseWriter<T> {
+    pub fn new(writer: T) -> Result<Self> {
+        let mut database = Self {
+            writer,
+            position: 0,
+            nodes: vec![],
+            items: vec![],
+        };
+
+        // root block
+        database.writer.write_all(&[0; NODE_HEADER_LENGTH])?;
+        database.position = NODE_HEADER_LENGTH as u32;
+        database.nodes.push(ResourceIndexNode {
+            node_start: 0,
+            node_type: 0,
+            node_next: database.position,
+            node_previous: 0,
+            node_end: database.position,
+        });
+
+        Ok(database)
+    }
+
+    /// Appends an asset, encoding its content
+    pub fn add(&mut self, asset: &AssetData) -> Result<()> {
+        let payload = asset.content.to_bytes()?;
+        self.add_payload(asset, &payload)
+    }
+
+    /// Appends an asset using the given payload as content
+    pub fn add_payload(&mut self, asset: &AssetData, payload: &[u8]) -> Result<()> {
+        self.add_with_block_header(asset, payload, &[0; NODE_HEADER_LENGTH])
+    }
+
+    /// Appends an asset using the given payload as content, preceded by the given block header
+    ///
+    /// Block headers of an existing database are read with `AssetData::read_block_header`.
+    pub fn add_with_block_header(
+        &mut self,
+        asset: &AssetData,
+        payload: &[u8],
+        block_header: &[u8; NODE_HEADER_LENGTH],
+    ) -> Result<()> {
+        let node_start = self.position;
+        let node_end = node_start + NODE_HEADER_LENGTH as u32;
+
+        let mut record = vec![];
+        asset.write(&mut record, payload)?;
+
+        self.writer.write_all(block_header)?;
+        self.writer.write_all(&record)?;
+        self.position = node_end + u32::try_from(record.len())?;
+
+        let node_previous = self.nodes.last().map_or(0, |node| node.node_start);
+        self.nodes.push(ResourceIndexNode {
+            node_start,
+            node_type: 0,
+            node_next: self.position,
+            node_previous,
+            node_end,
+        });
+
+        // characters are stored using their own name format
+        let (uid, name, char_name, trailer) = match asset.asset_type {
+            AssetType::Character => (
+                asset.uid.clone(),
+                ASSET_TYPE_CHAR_MESH.to_string(),
+                Some(asset.asset_name.clone()),
+                vec![0],
+            ),
+            _ => (
+                asset.uid.clone(),
+                asset.resource_name.clone(),
+                None,
+                vec![0, 0],
+            ),
+        };
+
+        self.items.push(ResourceIndexItem {
+            uid,
+            name,
+            char_name,
+            start: node_start,
+            unknown: 0,
+            size: u32::try_from(payload.len())?,
+            trailer,
+        });
+
+        Ok(())
+    }
+
+    /// Flushes the database and returns the matching index, leaving its header values zeroed
+    pub fn finish(self) -> Result<ResourceIndex> {
+        self.finish_with(0, 0)
+    }
+
+    /// Flushes the database and returns the matching index, using the given header values
+    ///
+    /// When repacking, these are taken from the original index, as found in
+    /// `ResourceIndex::unknown_1` and `ResourceIndex::unknown_2`.
+    pub fn finish_with(mut self, unknown_1: u32, unknown_2: u32) -> Result<ResourceIndex> {
+        self.writer.flush()?;
+        ResourceIndex::from_parts(unknown_1, unknown_2, self.nodes, self.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatabaseWriter, NODE_HEADER_LENGTH};
+    use crate::asset::PropertyBag;
+    use crate::asset::{TextContent, TextNode};
+    use crate::{AssetContent, AssetData, AssetType, ResourceIndex};
+    use ddsfile::{D3DFormat, Dds, NewD3dParams};
+    use std::io::Cursor;
+
+    fn assets() -> Vec<AssetData> {
+        vec![
+            AssetData::new(
+                AssetType::Sound,
+                50677,
+                "combat_pain_male_3",
+                AssetContent::Sound {
+                    filename: "combat_pain_male_3.ogg".into(),
+                    size: 4,
+                    bytes: b"OggS".to_vec(),
+                    properties: PropertyBag::new(),
+                },
+            ),
+            AssetData::new(
+                AssetType::Text,
+                59847,
+                "eng_faction_display_name",
+                AssetContent::Text {
+                    contents: vec![TextContent {
+                        refs: vec!["1".into(), "2".into()],
+                        nodes: vec![
+                            TextNode::Start,
+                            TextNode::Topic("Greeting".into()),
+                            TextNode::Content("[3] Hola, viajero".into()),
+                            TextNode::End,
+                        ],
+                        empty_lines: vec![3],
+                    }],
+                },
+            ),
+            AssetData::new(
+                AssetType::Image,
+                75879,
+                "loading",
+                AssetContent::Image {
+                    bytes: vec![0xFF, 0xD8, 0x00, 0xFF, 0xD9],
+                },
+            ),
+            AssetData::new(
+                AssetType::Texture,
+                1260,
+                "grass",
+                AssetContent::Texture {
+                    width: 4,
+                    height: 4,
+                    dds: Dds::new_d3d(NewD3dParams {
+                        height: 4,
+                        width: 4,
+                        depth: None,
+                        format: D3DFormat::DXT1,
+                        mipmap_levels: None,
+                        caps2: None,
+                    })
+                    .unwrap(),
+                    header: Box::default(),
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_write_database() {
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        for asset in assets() {
+            database.add(&asset).unwrap();
+        }
+        let index = database.finish().unwrap();
+        assert_eq!(index.bookmarks().len(), 4);
+
+        // blocks are chained without gaps
+        let graph = index.node_graph(sdb.len() as u64);
+        assert!(graph.is_valid());
+        assert_eq!(graph.chain, vec![0, 1, 2, 3, 4]);
+        assert!(graph.regions.is_empty());
+
+        // the generated index can be read back
+        let mut bytes = vec![];
+        index.write(&mut bytes).unwrap();
+        let index = ResourceIndex::read(bytes.as_slice()).unwrap();
+        assert_eq!(index.bookmarks().len(), 4);
+    }
+
+    #[test]
+    fn test_block_headers() {
+        let asset = AssetData::new(AssetType::Auth, 0, "pcauth", AssetContent::NotSupported);
+        assert_eq!(asset.uid, "pcauth");
+
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        database
+            .add_with_block_header(&asset, &[1, 2, 3], &[7; NODE_HEADER_LENGTH])
+            .unwrap();
+        let index = database.finish().unwrap();
+
+        let bookmark = &index.bookmarks()[0];
+        assert_eq!(bookmark.asset_type, AssetType::Auth);
+        assert_eq!(
+            AssetData::read_block_header(Cursor::new(&sdb), bookmark).unwrap(),
+            [7; NODE_HEADER_LENGTH]
+        );
+        assert_eq!(
+            AssetData::read_raw(Cursor::new(&sdb), bookmark).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_repack_database() {
+        let mut sdb = vec![];
+        let mut database = DatabaseWriter::new(&mut sdb).unwrap();
+        for asset in assets() {
+            database.add(&asset).unwrap();
+        }
+        let index = database.finish().unwrap();
+
+        let sound = index.get_by_resource_id(50677).unwrap();
+        let asset = AssetData::read(Cursor::new(&sdb), &sound).unwrap();
+        assert_eq!(asset.asset_type, AssetType::Sound);
+        assert_eq!(asset.asset_name, "combat_pain_male_3");
+        match &asset.content {
+            AssetContent::Sound {
+                filename, bytes, ..
+            } => {
+                assert_eq!(filename, "combat_pain_male_3.ogg");
+                assert_eq!(bytes, b"OggS");
+            }
+            _ => panic!("expected a sound"),
+        }
+
+        let text = index.get_by_resource_id(59847).unwrap();
+        let asset = AssetData::read(Cursor::new(&sdb), &text).unwrap();
+        match &asset.content {
+            AssetContent::Text { contents } => {
+                assert_eq!(contents.len(), 1);
+                assert_eq!(contents[0].refs, vec!["1", "2"]);
+                assert_eq!(contents[0].nodes.len(), 4);
+                assert!(
+                    matches!(&contents[0].nodes[2], TextNode::Content(c) if c == "[3] Hola, viajero")
+                );
+                assert!(matches!(&contents[0].nodes[3], TextNode::End));
+                assert_eq!(contents[0].empty_lines, vec![3]);
+            }
+            _ => panic!("expected a text"),
+        }
+
+        // reading every asset and writing them again generates the same file
+        let mut repacked = vec![];
+        let mut database = DatabaseWriter::new(&mut repacked).unwrap();
+        let mut bookmarks = index.bookmarks().to_vec();
+        bookmarks.sort_by_key(|bookmark| bookmark.node_start);
+        for bookmark in &bookmarks {
+            let asset = AssetData::read(Cursor::new(&sdb), bookmark).unwrap();
+            database.add(&asset).unwrap();
+        }
+        let repacked_index = database.finish_with(7, 9).unwrap();
+        assert_eq!(repacked, sdb);
+
+        // the index header values are kept
+        assert_eq!((index.unknown_1, index.unknown_2), (0, 0));
+        assert_eq!((repacked_index.unknown_1, repacked_index.unknown_2), (7, 9));
+        let mut bytes = vec![];
+        repacked_index.write(&mut bytes).unwrap();
+        let read = ResourceIndex::read(bytes.as_slice()).unwrap();
+        assert_eq!((read.unknown_1, read.unknown_2), (7, 9));
+    }
+}
@@ -1,23 +1,260 @@
+use crate::AssetType;
+
+/// A specialized result type for asset file operations
+pub type Result<T> = std::result::Result<T, AssetErrors>;
+
+/// Options used to control how parsers handle invalid entries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseOptions {
+    /// Fail on the first invalid entry instead of skipping it
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    /// Options that skip invalid entries, collecting them as diagnostics
+    pub fn lenient() -> Self {
+        Self { strict: false }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self { strict: true }
+    }
+}
+
+/// An entry skipped while parsing in lenient mode
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    /// Offset of the skipped entry within the parsed file
+    pub offset: u64,
+    /// The reason the entry was skipped
+    pub error: AssetErrors,
+}
+
+impl ParseDiagnostic {
+    /// Returns the error when parsing in strict mode, or the diagnostic otherwise
+    pub(crate) fn check(options: &ParseOptions, offset: u64, error: AssetErrors) -> Result<Self> {
+        if options.strict {
+            return Err(error);
+        }
+
+        Ok(Self { offset, error })
+    }
+}
+
 /// An enum that identifies possibles causes of errors during asset file parsing
+///
+/// Some variants only exist when their crate feature is enabled, and new
+/// ones may be added, so matches need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum AssetErrors {
-    ParserError,
+    ParserError(&'static str),
     UnknownAssetTypeError(String),
     UnknownAttributeError(String),
+    UnsupportedContentError(String),
+    UnknownResourceError(u32),
+    /// A content cannot be stored as an asset of the given type
+    ContentMismatch(AssetType),
+    /// A fixed sequence of bytes was not found where expected
+    BadMagic {
+        expected: Vec<u8>,
+        found: Vec<u8>,
+        offset: u64,
+    },
+    /// The data ended before a value could be read
+    UnexpectedEof {
+        offset: u64,
+    },
+    /// A size or length does not match the one declared elsewhere
+    SizeMismatch {
+        expected: u64,
+        found: u64,
+        offset: u64,
+    },
+    /// A read went past the boundaries of an asset
+    OutOfBounds {
+        limit: u64,
+        offset: u64,
+    },
+    /// A string could not be decoded
+    InvalidString {
+        offset: u64,
+    },
+    /// A resource name does not have the 'TYPE::NAME' form
+    InvalidResourceName(String),
+    /// An index item does not start where any node does
+    UnmatchedItem {
+        uid: String,
+        start: u64,
+    },
+    /// An index item starts at a node already used by another item, or where several nodes start
+    SharedNode {
+        uid: String,
+        start: u64,
+    },
+    /// An index node is not referenced by any item
+    UnmatchedNode {
+        start: u64,
+    },
+    /// A value does not fit in the field used to store it
+    Overflow(std::num::TryFromIntError),
+    DdsError(ddsfile::Error),
+    /// A texture uses a surface format that cannot be decoded
+    UnsupportedTextureFormat(String),
+    /// An image could not be encoded as PNG
+    #[cfg(feature = "image")]
+    PngError(png::EncodingError),
+    /// An Ogg Vorbis file could not be decoded
+    #[cfg(feature = "audio")]
+    VorbisError(lewton::VorbisError),
+    IoError(std::io::Error),
+}
+
+impl AssetErrors {
+    /// Returns the offset where the error was found, if known
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::BadMagic { offset, .. }
+            | Self::UnexpectedEof { offset }
+            | Self::SizeMismatch { offset, .. }
+            | Self::OutOfBounds { offset, .. }
+            | Self::InvalidString { offset } => Some(*offset),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for AssetErrors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::ParserError => write!(f, "Could not align data to the the expected format"),
+            Self::ParserError(context) => write!(
+                f,
+                "Could not align data to the the expected format: {}",
+                context
+            ),
             Self::UnknownAssetTypeError(asset_type) => {
                 write!(f, "unknown asset type: {}", asset_type)
             }
             Self::UnknownAttributeError(attr) => {
                 write!(f, "unknown asset attribute: {}", attr)
             }
+            Self::UnsupportedContentError(content) => {
+                write!(f, "content cannot be written: {}", content)
+            }
+            Self::UnknownResourceError(resource_id) => {
+                write!(f, "unknown resource id: {}", resource_id)
+            }
+            Self::ContentMismatch(asset_type) => {
+                write!(f, "content cannot be stored as a {:?} asset", asset_type)
+            }
+            Self::BadMagic {
+                expected,
+                found,
+                offset,
+            } => write!(
+                f,
+                "expected {:02X?} but found {:02X?} at offset {}",
+                expected, found, offset
+            ),
+            Self::UnexpectedEof { offset } => {
+                write!(f, "unexpected end of data at offset {}", offset)
+            }
+            Self::SizeMismatch {
+                expected,
+                found,
+                offset,
+            } => write!(
+                f,
+                "expected size {} but found {} at offset {}",
+                expected, found, offset
+            ),
+            Self::OutOfBounds { limit, offset } => {
+                write!(f, "offset {} is out of bounds (limit {})", offset, limit)
+            }
+            Self::InvalidString { offset } => {
+                write!(f, "invalid string at offset {}", offset)
+            }
+            Self::InvalidResourceName(name) => {
+                write!(f, "invalid resource name: {}", name)
+            }
+            Self::UnmatchedItem { uid, start } => {
+                write!(f, "no node found at offset {} for item {}", start, uid)
+            }
+            Self::SharedNode { uid, start } => {
+                write!(
+                    f,
+                    "node at offset {} is already used, found again for item {}",
+                    start, uid
+                )
+            }
+            Self::UnmatchedNode { start } => {
+                write!(f, "no item found for node at offset {}", start)
+            }
+            Self::Overflow(err) => write!(f, "value too large: {}", err),
+            Self::DdsError(err) => write!(f, "dds error: {}", err),
+            Self::UnsupportedTextureFormat(format) => {
+                write!(f, "unsupported texture format: {}", format)
+            }
+            #[cfg(feature = "image")]
+            Self::PngError(err) => write!(f, "png error: {}", err),
+            #[cfg(feature = "audio")]
+            Self::VorbisError(err) => write!(f, "vorbis error: {}", err),
+            Self::IoError(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "skipped entry at offset {}: {}", self.offset, self.error)
+    }
+}
+
+impl std::error::Error for AssetErrors {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Overflow(err) => Some(err),
+            Self::DdsError(err) => Some(err),
+            #[cfg(feature = "image")]
+            Self::PngError(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Self::VorbisError(err) => Some(err),
+            Self::IoError(err) => Some(err),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for AssetErrors {}
+impl From<std::io::Error> for AssetErrors {
+    fn from(err: std::io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+impl From<ddsfile::Error> for AssetErrors {
+    fn from(err: ddsfile::Error) -> Self {
+        Self::DdsError(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<lewton::VorbisError> for AssetErrors {
+    fn from(err: lewton::VorbisError) -> Self {
+        Self::VorbisError(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<png::EncodingError> for AssetErrors {
+    fn from(err: png::EncodingError) -> Self {
+        Self::PngError(err)
+    }
+}
+
+impl From<std::num::TryFromIntError> for AssetErrors {
+    fn from(err: std::num::TryFromIntError) -> Self {
+        Self::Overflow(err)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::errors::{AssetErrors, Result};
+use crate::{Archive, AssetBookmark, AssetData};
+use std::path::{Path, PathBuf};
+
+/// A struct holding every index/database pair found in a game installation folder
+pub struct GameInstallation {
+    pub archives: Vec<Archive>,
+    /// The index files that could not be opened, along with the reason
+    pub errors: Vec<(PathBuf, AssetErrors)>,
+}
+
+/// A bookmark found in a game installation, along with the archive containing it
+pub struct InstalledAsset<'a> {
+    pub archive: &'a Archive,
+    pub bookmark: &'a AssetBookmark,
+}
+
+impl InstalledAsset<'_> {
+    /// Reads the asset from its database file
+    pub fn read(&self) -> Result<AssetData> {
+        self.archive.read(self.bookmark)
+    }
+}
+
+impl GameInstallation {
+    /// Opens every `dataN` and `characters` file pair located in the given folder
+    ///
+    /// File names are matched regardless of their case, and archives are
+    /// sorted by their number, so `data10` comes after `data9`. A pair that
+    /// cannot be opened does not prevent opening the others; it is listed in
+    /// `errors` instead.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+
+        let mut paths = vec![];
+        for path in &files {
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            if Self::has_extension(path, "idx") && Self::is_resource_name(stem) {
+                let database_path = files.iter().find(|other| {
+                    other.file_stem() == path.file_stem() && Self::has_extension(other, "sdb")
+                });
+                if let Some(database_path) = database_path {
+                    paths.push((Self::sort_key(stem), path.clone(), database_path.clone()));
+                }
+            }
+        }
+
+        paths.sort();
+
+        let mut archives = vec![];
+        let mut errors = vec![];
+        for (_, index_path, database_path) in paths {
+            match Archive::open(&index_path, database_path) {
+                Ok(archive) => archives.push(archive),
+                Err(e) => errors.push((index_path, e)),
+            }
+        }
+
+        Ok(Self { archives, errors })
+    }
+
+    /// Checks whether a path has the given extension, regardless of its case
+    fn has_extension(path: &Path, extension: &str) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    }
+
+    /// Returns the key sorting resource files by name, then by number
+    fn sort_key(stem: &str) -> (String, u64) {
+        let stem = stem.to_ascii_lowercase();
+        let name = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        let number = stem[name.len()..].parse().unwrap_or(0);
+        (name.to_string(), number)
+    }
+
+    /// Checks whether a file name belongs to a resource file
+    fn is_resource_name(stem: &str) -> bool {
+        let stem = stem.to_ascii_lowercase();
+        if stem == "characters" {
+            return true;
+        }
+
+        match stem.strip_prefix("data") {
+            Some(number) => !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()),
+            None => false,
+        }
+    }
+
+    /// Retrieves an asset by its resource id, looking in every archive
+    pub fn get(&self, resource_id: u32) -> Option<InstalledAsset<'_>> {
+        self.archives.iter().find_map(|archive| {
+            archive
+                .index
+                .bookmark(resource_id)
+                .map(|bookmark| InstalledAsset { archive, bookmark })
+        })
+    }
+
+    /// Retrieves every asset with the given name, looking in every archive
+    pub fn find_by_name(&self, name: &str) -> Vec<InstalledAsset<'_>> {
+        self.archives
+            .iter()
+            .flat_map(|archive| {
+                archive
+                    .index
+                    .find_by_name(name)
+                    .map(move |bookmark| InstalledAsset { archive, bookmark })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GameInstallation;
+    use crate::{AssetContent, AssetData, AssetType, DatabaseWriter};
+    use std::fs::File;
+    use std::path::Path;
+
+    fn create_archive(dir: &Path, name: &str, assets: &[AssetData]) {
+        let database_path = dir.join(format!("{}.sdb", name));
+        let mut database = DatabaseWriter::new(File::create(database_path).unwrap()).unwrap();
+        for asset in assets {
+            database.add(asset).unwrap();
+        }
+        let index = database.finish().unwrap();
+        index
+            .write(File::create(dir.join(format!("{}.idx", name))).unwrap())
+            .unwrap();
+    }
+
+    fn image(resource_id: u32, name: &str) -> AssetData {
+        AssetData::new(
+            AssetType::Image,
+            resource_id,
+            name,
+            AssetContent::Image {
+                bytes: vec![0xFF, 0xD8, 0xFF, 0xD9],
+            },
+        )
+    }
+
+    #[test]
+    fn test_open_installation() {
+        let dir = std::env::temp_dir().join("regnumassets_installation");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        create_archive(&dir, "data3", &[image(10, "loading"), image(11, "splash")]);
+        create_archive(&dir, "data6", &[image(20, "loading")]);
+        create_archive(&dir, "backup", &[image(30, "loading")]);
+        create_archive(&dir, "data10", &[image(40, "intro")]);
+        create_archive(&dir, "DATA8", &[image(50, "intro")]);
+        std::fs::rename(dir.join("DATA8.sdb"), dir.join("DATA8.SDB")).unwrap();
+        std::fs::write(dir.join("data9.idx"), []).unwrap();
+        std::fs::write(dir.join("data9.sdb"), []).unwrap();
+
+        let installation = GameInstallation::open(&dir).unwrap();
+        let names: Vec<&str> = installation.archives.iter().map(|a| a.name()).collect();
+        assert_eq!(names, vec!["data3", "data6", "DATA8", "data10"]);
+        assert_eq!(installation.errors.len(), 1);
+        assert_eq!(installation.errors[0].0, dir.join("data9.idx"));
+
+        let found = installation.get(20).unwrap();
+        assert_eq!(found.archive.name(), "data6");
+        assert_eq!(found.read().unwrap().asset_name, "loading");
+        assert!(installation.get(30).is_none());
+
+        let found = installation.find_by_name("loading");
+        let names: Vec<&str> = found.iter().map(|a| a.archive.name()).collect();
+        assert_eq!(names, vec!["data3", "data6"]);
+    }
+}
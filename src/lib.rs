@@ -1,6 +1,20 @@
 pub mod asset;
+pub mod database;
 pub mod errors;
+pub mod installation;
+pub mod parser;
+pub mod reader;
 pub mod resource;
 
-pub use asset::{bookmark::AssetBookmark, content::AssetContent, data::AssetData, AssetType};
-pub use resource::{get_resource_filename, index::ResourceIndex, ResourceFormat, ResourceType};
+pub use asset::{
+    bookmark::AssetBookmark,
+    content::AssetContent,
+    data::{AssetData, AssetHeader},
+    AssetType,
+};
+pub use database::{Archive, Database, DatabaseWriter, MappedDatabase};
+pub use errors::{ParseDiagnostic, ParseOptions};
+pub use installation::GameInstallation;
+pub use resource::{
+    get_resource_filename, index::ResourceIndex, names::NamePattern, ResourceFormat, ResourceType,
+};
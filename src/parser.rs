@@ -0,0 +1,182 @@
+use crate::errors::AssetErrors;
+use encoding_rs::WINDOWS_1252;
+use nom::bytes::complete::take;
+use nom::error::{ErrorKind, ParseError};
+use nom::IResult;
+
+/// The result returned by parsers working on byte slices
+pub type SliceResult<'a, O> = IResult<&'a [u8], O, SliceError<'a>>;
+
+/// An error returned by parsers working on byte slices
+///
+/// The error keeps the input remaining where it happened, so its offset
+/// can be obtained once the whole slice is known.
+#[derive(Debug)]
+pub struct SliceError<'a> {
+    pub input: &'a [u8],
+    pub kind: SliceErrorKind,
+}
+
+/// An enum listing the causes of a slice parser error
+#[derive(Debug)]
+pub enum SliceErrorKind {
+    /// The data ended before a value could be parsed
+    Eof,
+    /// A fixed sequence of bytes was not found
+    BadMagic { expected: Vec<u8>, found: Vec<u8> },
+    /// A string could not be decoded
+    InvalidString,
+    /// The parser went past a limit, given as the length of the input remaining at that point
+    OutOfBounds { limit: usize },
+    /// An error not related to a position in the input
+    Asset(AssetErrors),
+    /// An error returned by a nom combinator
+    Nom(ErrorKind),
+}
+
+impl<'a> SliceError<'a> {
+    /// Converts the error, using the slice given to the parser to locate it
+    pub fn into_asset_error(self, data: &[u8]) -> AssetErrors {
+        let offset = Self::offset(data, self.input);
+
+        match self.kind {
+            SliceErrorKind::Eof => AssetErrors::UnexpectedEof { offset },
+            SliceErrorKind::BadMagic { expected, found } => AssetErrors::BadMagic {
+                expected,
+                found,
+                offset,
+            },
+            SliceErrorKind::InvalidString => AssetErrors::InvalidString { offset },
+            SliceErrorKind::OutOfBounds { limit } => AssetErrors::OutOfBounds {
+                limit: Self::offset(data, &data[data.len() - limit..]),
+                offset,
+            },
+            SliceErrorKind::Asset(err) => err,
+            SliceErrorKind::Nom(_) => AssetErrors::ParserError("unexpected data"),
+        }
+    }
+
+    fn offset(data: &[u8], input: &[u8]) -> u64 {
+        data.len().saturating_sub(input.len()) as u64
+    }
+}
+
+impl<'a> ParseError<&'a [u8]> for SliceError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        let kind = match kind {
+            ErrorKind::Eof => SliceErrorKind::Eof,
+            kind => SliceErrorKind::Nom(kind),
+        };
+
+        Self { input, kind }
+    }
+
+    fn append(_input: &'a [u8], _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// Converts the error returned by a slice parser, using the slice given to it
+pub fn into_asset_error(err: nom::Err<SliceError>, data: &[u8]) -> AssetErrors {
+    match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => err.into_asset_error(data),
+        nom::Err::Incomplete(_) => AssetErrors::UnexpectedEof {
+            offset: data.len() as u64,
+        },
+    }
+}
+
+/// Returns an error located at the given input
+pub fn fail<O>(input: &[u8], kind: SliceErrorKind) -> SliceResult<'_, O> {
+    Err(nom::Err::Error(SliceError { input, kind }))
+}
+
+/// Recognizes a fixed sequence of bytes
+pub fn magic<'a>(expected: &'static [u8]) -> impl Fn(&'a [u8]) -> SliceResult<'a, &'a [u8]> {
+    move |input: &'a [u8]| {
+        let Some(found) = input.get(..expected.len()) else {
+            return fail(input, SliceErrorKind::Eof);
+        };
+
+        if found != expected {
+            return fail(
+                input,
+                SliceErrorKind::BadMagic {
+                    expected: expected.to_vec(),
+                    found: found.to_vec(),
+                },
+            );
+        }
+
+        Ok((&input[expected.len()..], found))
+    }
+}
+
+/// Takes a fixed number of bytes
+pub fn array<const N: usize>(input: &[u8]) -> SliceResult<'_, [u8; N]> {
+    let (rest, bytes) = take(N)(input)?;
+    let mut buffer = [0; N];
+    buffer.copy_from_slice(bytes);
+    Ok((rest, buffer))
+}
+
+/// Recognizes an UTF-8 string of the given length
+pub fn utf8_string<'a>(length: usize) -> impl Fn(&'a [u8]) -> SliceResult<'a, String> {
+    move |input: &'a [u8]| {
+        let (rest, bytes) = take(length)(input)?;
+        match std::str::from_utf8(bytes) {
+            Ok(value) => Ok((rest, value.to_string())),
+            Err(_) => fail(input, SliceErrorKind::InvalidString),
+        }
+    }
+}
+
+/// Recognizes a Windows-1252 string of the given length
+pub fn windows_1252<'a>(length: usize) -> impl Fn(&'a [u8]) -> SliceResult<'a, String> {
+    move |input: &'a [u8]| {
+        let (rest, bytes) = take(length)(input)?;
+        let (value, _, _) = WINDOWS_1252.decode(bytes);
+        Ok((rest, value.into_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{into_asset_error, magic, utf8_string, windows_1252};
+    use crate::errors::AssetErrors;
+
+    #[test]
+    fn test_parse_strings() {
+        let data = b"resource_1Ca\xf1ada";
+        let (rest, uid) = utf8_string(10)(data).unwrap();
+        assert_eq!(uid, "resource_1");
+        let (rest, name) = windows_1252(6)(rest).unwrap();
+        assert_eq!(name, "Cañada");
+        assert!(rest.is_empty());
+
+        let err = utf8_string(64)(data).unwrap_err();
+        match into_asset_error(err, data) {
+            AssetErrors::UnexpectedEof { offset } => assert_eq!(offset, 0),
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_magic() {
+        let data = b"..PAIR";
+        let err = magic(b"RIAP")(&data[2..]).unwrap_err();
+
+        match into_asset_error(err, data) {
+            AssetErrors::BadMagic {
+                expected,
+                found,
+                offset,
+            } => {
+                assert_eq!(expected, b"RIAP");
+                assert_eq!(found, b"PAIR");
+                assert_eq!(offset, 2);
+            }
+            _ => panic!("expected a bad magic error"),
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::errors::{AssetErrors, Result};
+use encoding_rs::WINDOWS_1252;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+/// A reader wrapper keeping track of the current offset, used to report errors
+pub struct OffsetReader<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R: Read> OffsetReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_offset(inner, 0)
+    }
+
+    /// Creates a reader whose first byte is located at the given offset
+    pub fn with_offset(inner: R, offset: u64) -> Self {
+        Self { inner, offset }
+    }
+
+    /// Returns the offset of the next byte to be read
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Fills the buffer, failing with the offset where the data ended
+    pub fn read_full(&mut self, buffer: &mut [u8]) -> Result<()> {
+        let offset = self.offset;
+        match self.read_exact(buffer) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                Err(AssetErrors::UnexpectedEof { offset })
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Reads the given number of bytes
+    ///
+    /// The buffer grows as data arrives, so a corrupt length does not
+    /// allocate more memory than the data available.
+    pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>> {
+        let offset = self.offset;
+        let mut buffer = vec![];
+        self.by_ref().take(length as u64).read_to_end(&mut buffer)?;
+
+        if buffer.len() < length {
+            return Err(AssetErrors::UnexpectedEof { offset });
+        }
+
+        Ok(buffer)
+    }
+
+    /// Reads a fixed number of bytes
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buffer = [0; N];
+        self.read_full(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Reads an UTF-8 string of the given length
+    pub fn read_string(&mut self, length: usize) -> Result<String> {
+        let offset = self.offset;
+        let buffer = self.read_bytes(length)?;
+        String::from_utf8(buffer).map_err(|_| AssetErrors::InvalidString { offset })
+    }
+
+    /// Reads a Windows-1252 string of the given length
+    pub fn read_windows_1252(&mut self, length: usize) -> Result<String> {
+        let buffer = self.read_bytes(length)?;
+        let (value, _, _) = WINDOWS_1252.decode(&buffer);
+        Ok(value.into_owned())
+    }
+
+    /// Reads a single byte
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let mut buffer = [0; 1];
+        self.read_full(&mut buffer)?;
+        Ok(buffer[0])
+    }
+
+    /// Reads a little endian u16
+    pub fn read_u16(&mut self) -> Result<u16> {
+        let mut buffer = [0; 2];
+        self.read_full(&mut buffer)?;
+        Ok(u16::from_le_bytes(buffer))
+    }
+
+    /// Reads a little endian u32
+    pub fn read_u32(&mut self) -> Result<u32> {
+        let mut buffer = [0; 4];
+        self.read_full(&mut buffer)?;
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    /// Checks that the next bytes match the expected ones
+    pub fn expect(&mut self, expected: &[u8]) -> Result<()> {
+        let offset = self.offset;
+        let mut found = vec![0; expected.len()];
+        self.read_full(&mut found)?;
+
+        if found != expected {
+            return Err(AssetErrors::BadMagic {
+                expected: expected.to_vec(),
+                found,
+                offset,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> OffsetReader<R> {
+    /// Creates a reader starting at the current position of the inner reader
+    pub fn from_current(mut inner: R) -> Result<Self> {
+        let offset = inner.stream_position()?;
+        Ok(Self::with_offset(inner, offset))
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for OffsetReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.offset = self.inner.seek(pos)?;
+        Ok(self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OffsetReader;
+    use crate::errors::AssetErrors;
+
+    #[test]
+    fn test_read_values() {
+        let data = [1, 2, 0, 3, 0, 0, 0];
+        let mut reader = OffsetReader::with_offset(data.as_slice(), 10);
+        assert_eq!(reader.read_u8().unwrap(), 1);
+        assert_eq!(reader.read_u16().unwrap(), 2);
+        assert_eq!(reader.read_u32().unwrap(), 3);
+        assert_eq!(reader.offset(), 17);
+    }
+
+    #[test]
+    fn test_unexpected_eof() {
+        let data = [1, 2, 3];
+        let mut reader = OffsetReader::new(data.as_slice());
+        reader.read_u8().unwrap();
+
+        match reader.read_u32() {
+            Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, 1),
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_read_strings() {
+        let data = b"resource_1Ca\xf1ada";
+        let mut reader = OffsetReader::new(data.as_slice());
+        assert_eq!(reader.read_string(10).unwrap(), "resource_1");
+        assert_eq!(reader.read_windows_1252(6).unwrap(), "Cañada");
+
+        let mut reader = OffsetReader::new(data.as_slice());
+        match reader.read_bytes(64) {
+            Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, 0),
+            _ => panic!("expected an unexpected eof error"),
+        }
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let data = b"PAIR";
+        let mut reader = OffsetReader::new(data.as_slice());
+
+        match reader.expect(b"RIAP") {
+            Err(AssetErrors::BadMagic {
+                expected,
+                found,
+                offset,
+            }) => {
+                assert_eq!(expected, b"RIAP");
+                assert_eq!(found, b"PAIR");
+                assert_eq!(offset, 0);
+            }
+            _ => panic!("expected a bad magic error"),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use super::node::ResourceIndexNode;
+use std::collections::{BTreeMap, HashMap};
+
+/// An issue found while checking the nodes of an index
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeIssue {
+    /// A node points to an offset where no node starts, and no node starts after it
+    DanglingNext { node: usize, target: u64 },
+    /// A node points to an offset where no node starts, the walk goes on with the first node found after it
+    Gap { node: usize, target: u64, next: u64 },
+    /// A node does not point back to the node linking to it
+    BrokenPrevious {
+        node: usize,
+        expected: u64,
+        found: u64,
+    },
+    /// The chain goes back to a node already visited
+    Cycle { node: usize, target: u64 },
+    /// Two nodes start at the same offset
+    Duplicate { first: usize, second: usize },
+    /// A node block goes past the start of the following one
+    Overlap { first: usize, second: usize },
+    /// A node cannot be reached by following the chain from the root
+    Unreachable { node: usize },
+}
+
+/// An enum listing the kinds of unused regions found in a database file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RegionKind {
+    /// Bytes not covered by any node block
+    Free,
+    /// Bytes covered by a node that cannot be reached from the root
+    Orphaned { node: usize, node_type: u32 },
+    /// Bytes of a node block found after the record it holds
+    ///
+    /// Space given up by a relocated record is found here, along with
+    /// the old record itself.
+    Slack { node: usize },
+}
+
+/// A range of bytes in a database file that is not part of the node chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub start: u64,
+    pub end: u64,
+    pub kind: RegionKind,
+}
+
+/// The result of walking the nodes of an index as a linked list
+///
+/// Nodes are expected to form a chain starting at the root node, where
+/// `node_next` is the start of the following node (or the end of the
+/// database for the last one) and `node_previous` is the start of the
+/// preceding node. Each node owns the block `node_start..node_next`.
+///
+/// This layout is the one written by `DatabaseWriter`, and has not been
+/// confirmed on game files. When `node_next` does not match the start of
+/// a node, the walk goes on with the first node starting after it and
+/// reports a `Gap`, so a different layout shows up as a list of issues
+/// instead of cutting the chain short.
+///
+/// The meaning of `node_type` is not known either, so nodes are walked
+/// the same way whatever their type. Nodes are grouped by type in
+/// `types`, and orphaned regions keep the type of their node.
+#[derive(Debug)]
+pub struct NodeGraph {
+    /// Node positions, in chain order
+    pub chain: Vec<usize>,
+    pub issues: Vec<NodeIssue>,
+    /// Node positions, grouped by node type
+    pub types: BTreeMap<u32, Vec<usize>>,
+    /// Regions of the database not used by the chain, sorted by offset
+    pub regions: Vec<Region>,
+}
+
+impl NodeGraph {
+    /// Walks the node chain of a database with the given length
+    pub fn new(nodes: &[ResourceIndexNode], database_length: u64) -> Self {
+        let mut issues = vec![];
+
+        let mut types: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        let mut starts: HashMap<u64, usize> = HashMap::new();
+        for (pos, node) in nodes.iter().enumerate() {
+            types.entry(node.node_type).or_default().push(pos);
+            if let Some(&first) = starts.get(&(node.node_start as u64)) {
+                issues.push(NodeIssue::Duplicate { first, second: pos });
+            } else {
+                starts.insert(node.node_start as u64, pos);
+            }
+        }
+
+        // node starts in offset order, to find the node following a gap
+        let mut sorted: Vec<(u64, usize)> =
+            starts.iter().map(|(&start, &pos)| (start, pos)).collect();
+        sorted.sort();
+
+        // the root is the node found at the lowest offset
+        let root = (0..nodes.len()).min_by_key(|&i| nodes[i].node_start);
+
+        let mut chain = vec![];
+        let mut visited = vec![false; nodes.len()];
+        let mut current = root;
+        while let Some(pos) = current {
+            visited[pos] = true;
+            chain.push(pos);
+            current = None;
+
+            let node = &nodes[pos];
+            let target = node.node_next as u64;
+            if target == database_length {
+                break;
+            }
+
+            let next = match starts.get(&target) {
+                Some(&next) => Some(next),
+                None => {
+                    let found = sorted.get(sorted.partition_point(|&(start, _)| start < target));
+                    if let Some(&(start, _)) = found {
+                        issues.push(NodeIssue::Gap {
+                            node: pos,
+                            target,
+                            next: start,
+                        });
+                    }
+                    found.map(|&(_, next)| next)
+                }
+            };
+
+            match next {
+                Some(next) if visited[next] => {
+                    issues.push(NodeIssue::Cycle { node: pos, target });
+                }
+                Some(next) => {
+                    if nodes[next].node_previous != node.node_start {
+                        issues.push(NodeIssue::BrokenPrevious {
+                            node: next,
+                            expected: node.node_start as u64,
+                            found: nodes[next].node_previous as u64,
+                        });
+                    }
+                    current = Some(next);
+                }
+                None => issues.push(NodeIssue::DanglingNext { node: pos, target }),
+            }
+        }
+
+        let mut regions = vec![];
+        for pos in (0..nodes.len()).filter(|&i| !visited[i]) {
+            issues.push(NodeIssue::Unreachable { node: pos });
+            regions.push(Region {
+                start: nodes[pos].node_start as u64,
+                end: nodes[pos].node_next as u64,
+                kind: RegionKind::Orphaned {
+                    node: pos,
+                    node_type: nodes[pos].node_type,
+                },
+            });
+        }
+
+        // look for overlaps and gaps between blocks
+        let mut order: Vec<usize> = (0..nodes.len()).collect();
+        order.sort_by_key(|&i| (nodes[i].node_start, nodes[i].node_next));
+
+        let mut end = 0;
+        let mut last: Option<usize> = None;
+        for &pos in &order {
+            let node = &nodes[pos];
+            let (start, next) = (node.node_start as u64, node.node_next as u64);
+
+            match last {
+                Some(first) if start < end && nodes[first].node_start != node.node_start => {
+                    issues.push(NodeIssue::Overlap { first, second: pos });
+                }
+                _ if start > end => regions.push(Region {
+                    start: end,
+                    end: start,
+                    kind: RegionKind::Free,
+                }),
+                _ => {}
+            }
+
+            if next > end {
+                end = next;
+                last = Some(pos);
+            }
+        }
+
+        if end < database_length {
+            regions.push(Region {
+                start: end,
+                end: database_length,
+                kind: RegionKind::Free,
+            });
+        }
+
+        regions.sort_by_key(|r| (r.start, r.end));
+
+        Self {
+            chain,
+            issues,
+            types,
+            regions,
+        }
+    }
+
+    /// Reports the bytes of a node block found after the end of its record
+    ///
+    /// Nodes are not read from the database while walking the chain, so
+    /// the end of each record is given by the caller, as done by
+    /// `Archive::check`.
+    pub fn add_slack(&mut self, nodes: &[ResourceIndexNode], node: usize, record_end: u64) {
+        let node_next = nodes[node].node_next as u64;
+        if record_end >= node_next {
+            return;
+        }
+
+        self.regions.push(Region {
+            start: record_end,
+            end: node_next,
+            kind: RegionKind::Slack { node },
+        });
+        self.regions.sort_by_key(|r| (r.start, r.end));
+    }
+
+    /// Checks whether the chain covers every node without issues
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeGraph, NodeIssue, Region, RegionKind};
+    use crate::resource::node::ResourceIndexNode;
+
+    fn node(node_start: u32, node_next: u32, node_previous: u32) -> ResourceIndexNode {
+        ResourceIndexNode {
+            node_start,
+            node_type: 0,
+            node_next,
+            node_previous,
+            node_end: node_start + 23,
+        }
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        // nodes are not stored in chain order
+        let nodes = [node(100, 200, 0), node(0, 100, 0), node(200, 300, 100)];
+        let graph = NodeGraph::new(&nodes, 300);
+
+        assert!(graph.is_valid());
+        assert_eq!(graph.chain, vec![1, 0, 2]);
+        assert!(graph.regions.is_empty());
+    }
+
+    #[test]
+    fn test_broken_chain() {
+        let nodes = [
+            node(0, 100, 0),
+            node(100, 250, 0),
+            node(200, 300, 50),
+            node(400, 500, 300),
+        ];
+        let graph = NodeGraph::new(&nodes, 600);
+
+        // the walk goes on after the gap left by the second node
+        assert_eq!(graph.chain, vec![0, 1, 3]);
+        assert_eq!(
+            graph.issues,
+            vec![
+                NodeIssue::Gap {
+                    node: 1,
+                    target: 250,
+                    next: 400
+                },
+                NodeIssue::BrokenPrevious {
+                    node: 3,
+                    expected: 100,
+                    found: 300
+                },
+                NodeIssue::DanglingNext {
+                    node: 3,
+                    target: 500
+                },
+                NodeIssue::Unreachable { node: 2 },
+                NodeIssue::Overlap {
+                    first: 1,
+                    second: 2
+                },
+            ]
+        );
+        assert_eq!(
+            graph.regions,
+            vec![
+                Region {
+                    start: 200,
+                    end: 300,
+                    kind: RegionKind::Orphaned {
+                        node: 2,
+                        node_type: 0
+                    }
+                },
+                Region {
+                    start: 300,
+                    end: 400,
+                    kind: RegionKind::Free
+                },
+                Region {
+                    start: 500,
+                    end: 600,
+                    kind: RegionKind::Free
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_node_types() {
+        let mut nodes = [node(0, 300, 0), node(300, 400, 0), node(100, 200, 0)];
+        nodes[1].node_type = 4;
+        nodes[2].node_type = 4;
+        let graph = NodeGraph::new(&nodes, 400);
+
+        assert_eq!(graph.types.get(&0), Some(&vec![0]));
+        assert_eq!(graph.types.get(&4), Some(&vec![1, 2]));
+        assert_eq!(
+            graph.regions[0].kind,
+            RegionKind::Orphaned {
+                node: 2,
+                node_type: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_slack() {
+        let nodes = [node(0, 100, 0), node(100, 200, 0)];
+        let mut graph = NodeGraph::new(&nodes, 200);
+        graph.add_slack(&nodes, 0, 60);
+        graph.add_slack(&nodes, 1, 200);
+
+        assert!(graph.is_valid());
+        assert_eq!(
+            graph.regions,
+            vec![Region {
+                start: 60,
+                end: 100,
+                kind: RegionKind::Slack { node: 0 }
+            }]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let nodes = [node(0, 100, 0), node(100, 200, 0), node(200, 100, 100)];
+        let graph = NodeGraph::new(&nodes, 300);
+
+        assert_eq!(graph.chain, vec![0, 1, 2]);
+        assert_eq!(
+            graph.issues,
+            vec![NodeIssue::Cycle {
+                node: 2,
+                target: 100
+            }]
+        );
+    }
+
+    #[test]
+    fn test_broken_previous() {
+        let nodes = [node(0, 100, 0), node(100, 200, 50)];
+        let graph = NodeGraph::new(&nodes, 200);
+
+        assert_eq!(
+            graph.issues,
+            vec![NodeIssue::BrokenPrevious {
+                node: 1,
+                expected: 0,
+                found: 50
+            }]
+        );
+    }
+}
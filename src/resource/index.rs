@@ -1,30 +1,63 @@
+use super::graph::NodeGraph;
 use super::item::ResourceIndexItem;
+use super::names::{NameIndex, NamePattern};
 use super::node::ResourceIndexNode;
 use crate::asset::{bookmark::AssetBookmark, AssetType};
 use crate::asset::{ASSET_TYPE_CHAR_MESH, ASSET_TYPE_PCAUTH};
-use crate::errors::AssetErrors;
-use anyhow::{anyhow, Result};
-use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::Read;
+use crate::database::NODE_HEADER_LENGTH;
+use crate::errors::{AssetErrors, ParseDiagnostic, ParseOptions, Result};
+use crate::parser::{into_asset_error, SliceResult};
+use crate::reader::OffsetReader;
+use byteorder::{LittleEndian, WriteBytesExt};
+use nom::{multi::length_count, number::complete::le_u32, Parser};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+
+/// Length of the index header, preceding the list of nodes
+const INDEX_HEADER_LENGTH: u64 = 4 * 3;
+/// Length of a single header node
+const INDEX_NODE_LENGTH: u64 = 4 * 5;
 
 /// A struct representing the elements contained within a resource index file
 pub struct ResourceIndex {
-    pub bookmarks: Vec<AssetBookmark>,
+    /// The bookmarks, sorted by resource id
+    ///
+    /// Kept private, as the lookups store positions into this list.
+    bookmarks: Vec<AssetBookmark>,
+    /// The header nodes, in file order
+    pub nodes: Vec<ResourceIndexNode>,
+    /// The body items, in file order
+    pub items: Vec<ResourceIndexItem>,
+    /// Items skipped while parsing in lenient mode
+    pub diagnostics: Vec<ParseDiagnostic>,
+
+    // TODO
+    pub unknown_1: u32,
+    pub unknown_2: u32,
+
+    options: ParseOptions,
+    names: NameIndex,
+    /// Bookmark positions sorted by uid
+    uids: Vec<usize>,
+    /// Bookmark positions sorted by node start
+    starts: Vec<usize>,
 }
 
 impl ResourceIndex {
-    pub fn read<T: Read>(mut reader: T) -> Result<Self> {
-        // total nodes
-        let mut buffer = [0; 4 * 3];
-        reader.read(&mut buffer)?;
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u32>();
-            values
-        };
+    pub fn read<T: Read>(reader: T) -> Result<Self> {
+        Self::read_with_options(reader, ParseOptions::default())
+    }
 
-        let [_unknown_1, _unknown_2, total_nodes] = values[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("resource index total nodes"));
-        };
+    /// Reads an index, skipping invalid items unless parsing in strict mode
+    pub fn read_with_options<T: Read>(reader: T, options: ParseOptions) -> Result<Self> {
+        let mut reader = OffsetReader::new(reader);
+
+        // TODO: ???
+        let unknown_1 = reader.read_u32()?;
+        let unknown_2 = reader.read_u32()?;
+
+        // total nodes
+        let total_nodes = reader.read_u32()?;
 
         // parse header nodes
         let mut nodes = vec![];
@@ -34,7 +67,7 @@ impl ResourceIndex {
         }
 
         // total items
-        let total_items = reader.read_u32::<LittleEndian>()?;
+        let total_items = reader.read_u32()?;
 
         // parse body items
         let mut items = vec![];
@@ -43,92 +76,684 @@ impl ResourceIndex {
             items.push(item);
         }
 
-        // sort by start position
-        nodes.sort_by(|a, b| a.node_start.cmp(&b.node_start));
-        items.sort_by(|a, b| a.start.cmp(&b.start));
+        Self::build(unknown_1, unknown_2, nodes, items, options)
+    }
+
+    /// Parses an index from a byte slice
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        Self::parse_with_options(data, ParseOptions::default())
+    }
+
+    /// Parses an index from a byte slice, skipping invalid items unless parsing in strict mode
+    pub fn parse_with_options(data: &[u8], options: ParseOptions) -> Result<Self> {
+        let (_, (unknown_1, unknown_2, nodes, items)) =
+            Self::parse_parts(data).map_err(|err| into_asset_error(err, data))?;
+
+        Self::build(unknown_1, unknown_2, nodes, items, options)
+    }
+
+    /// Parses the header values, nodes and items of an index
+    fn parse_parts(
+        input: &[u8],
+    ) -> SliceResult<'_, (u32, u32, Vec<ResourceIndexNode>, Vec<ResourceIndexItem>)> {
+        // TODO: ???
+        let (input, (unknown_1, unknown_2)) = (le_u32, le_u32).parse(input)?;
+
+        let (input, nodes) = length_count(le_u32, ResourceIndexNode::parse).parse(input)?;
+        let (input, items) = length_count(le_u32, ResourceIndexItem::parse).parse(input)?;
+
+        Ok((input, (unknown_1, unknown_2, nodes, items)))
+    }
+
+    /// Builds an index from its header values, nodes and items
+    pub fn from_parts(
+        unknown_1: u32,
+        unknown_2: u32,
+        nodes: Vec<ResourceIndexNode>,
+        items: Vec<ResourceIndexItem>,
+    ) -> Result<Self> {
+        Self::build(unknown_1, unknown_2, nodes, items, ParseOptions::default())
+    }
+
+    fn build(
+        unknown_1: u32,
+        unknown_2: u32,
+        nodes: Vec<ResourceIndexNode>,
+        items: Vec<ResourceIndexItem>,
+        options: ParseOptions,
+    ) -> Result<Self> {
+        let (bookmarks, diagnostics) = Self::read_bookmarks(&nodes, &items, &options)?;
+
+        let mut index = Self {
+            bookmarks,
+            nodes,
+            items,
+            diagnostics,
+            unknown_1,
+            unknown_2,
+            options,
+            names: NameIndex::new(&[]),
+            uids: vec![],
+            starts: vec![],
+        };
+        index.update_lookups();
+
+        Ok(index)
+    }
+
+    /// Pairs header nodes with body items to generate the list of bookmarks
+    fn read_bookmarks(
+        nodes: &[ResourceIndexNode],
+        items: &[ResourceIndexItem],
+        options: &ParseOptions,
+    ) -> Result<(Vec<AssetBookmark>, Vec<ParseDiagnostic>)> {
+        // item offsets, used to report invalid items
+        let mut item_offsets = vec![];
+        let mut offset = INDEX_HEADER_LENGTH + nodes.len() as u64 * INDEX_NODE_LENGTH + 4;
+        for item in items {
+            item_offsets.push(offset);
+            offset += item.length() as u64;
+        }
+
+        // nodes are joined with items by their start offset
+        let mut starts: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (pos, node) in nodes.iter().enumerate() {
+            starts.entry(node.node_start).or_default().push(pos);
+        }
 
+        let mut used = vec![false; nodes.len()];
+        let mut referenced = vec![false; nodes.len()];
         let mut bookmarks = vec![];
-        for i in 0..total_items {
-            let node = &nodes[(i + 1) as usize];
+        let mut diagnostics = vec![];
+        for (item_pos, item) in items.iter().enumerate() {
+            let found = starts.get(&item.start);
+            found
+                .into_iter()
+                .flatten()
+                .for_each(|&pos| referenced[pos] = true);
+
+            let node_pos = match found.map(Vec::as_slice) {
+                Some(&[pos]) if !used[pos] => Ok(pos),
+                Some(_) => Err(AssetErrors::SharedNode {
+                    uid: item.uid.clone(),
+                    start: item.start as u64,
+                }),
+                None => Err(AssetErrors::UnmatchedItem {
+                    uid: item.uid.clone(),
+                    start: item.start as u64,
+                }),
+            };
+            let node_pos = match node_pos {
+                Ok(pos) => pos,
+                Err(err) => {
+                    diagnostics.push(ParseDiagnostic::check(
+                        options,
+                        item_offsets[item_pos],
+                        err,
+                    )?);
+                    continue;
+                }
+            };
+            used[node_pos] = true;
+
+            let node = &nodes[node_pos];
             let node_start = node.node_start as usize;
             let node_end = node.node_end as usize;
             let node_next = node.node_next as usize;
 
-            let item = &items[i as usize];
-
             // parse resource id
-            let resource_id = {
-                if let Some(pos) = item.uid.rfind(|c| c == '_') {
-                    if let Ok(value) = item.uid[pos + 1..].parse::<u32>() {
-                        Some(value)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            };
-
-            let (name, asset_type): (Option<String>, AssetType) = {
-                if ASSET_TYPE_CHAR_MESH == item.name {
-                    (item.char_name.clone(), AssetType::Character)
-                } else if item.uid == ASSET_TYPE_PCAUTH {
-                    (Some(item.uid.clone()), AssetType::Auth)
-                } else {
-                    let parts: Vec<&str> = item.name.split("::").collect();
-                    let asset_type = AssetType::try_from(parts[0])?;
-                    (Some(parts[1].into()), asset_type)
+            let resource_id = item
+                .uid
+                .rfind('_')
+                .and_then(|pos| item.uid[pos + 1..].parse::<u32>().ok());
+
+            let (name, asset_type) = match Self::parse_name(item) {
+                Ok(value) => value,
+                Err(err) => {
+                    diagnostics.push(ParseDiagnostic::check(
+                        options,
+                        item_offsets[item_pos],
+                        err,
+                    )?);
+                    continue;
                 }
             };
 
             let size = item.size;
 
             // TODO
-            assert_eq!(node_end - node_start, 23);
+            if node_end.checked_sub(node_start) != Some(NODE_HEADER_LENGTH) {
+                let offset = INDEX_HEADER_LENGTH + node_pos as u64 * INDEX_NODE_LENGTH;
+                let err = AssetErrors::SizeMismatch {
+                    expected: NODE_HEADER_LENGTH as u64,
+                    found: node_end.saturating_sub(node_start) as u64,
+                    offset,
+                };
+                diagnostics.push(ParseDiagnostic::check(options, offset, err)?);
+                continue;
+            }
 
             bookmarks.push(AssetBookmark {
+                uid: item.uid.clone(),
                 resource_id,
                 asset_type,
                 name,
+                resource_name: item.name.clone(),
                 node_start,
                 node_end,
                 node_next,
+                node_previous: node.node_previous as usize,
+                node_type: node.node_type,
+                item_start: item.start as usize,
+                item_unknown: item.unknown,
                 size,
             });
         }
 
+        // nodes without items do not prevent reading the index, so they are
+        // only reported in lenient mode
+        if !options.strict {
+            let root = (0..nodes.len()).min_by_key(|&i| nodes[i].node_start);
+            for pos in (0..nodes.len()).filter(|&i| Some(i) != root && !referenced[i]) {
+                diagnostics.push(ParseDiagnostic {
+                    offset: INDEX_HEADER_LENGTH + pos as u64 * INDEX_NODE_LENGTH,
+                    error: AssetErrors::UnmatchedNode {
+                        start: nodes[pos].node_start as u64,
+                    },
+                });
+            }
+        }
+
         // order by resource id
-        bookmarks.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+        bookmarks.sort_by_key(|b| b.resource_id);
 
-        Ok(Self { bookmarks })
+        Ok((bookmarks, diagnostics))
+    }
+
+    /// Retrieves the asset name and type of an item
+    fn parse_name(item: &ResourceIndexItem) -> Result<(Option<String>, AssetType)> {
+        if ASSET_TYPE_CHAR_MESH == item.name {
+            return Ok((item.char_name.clone(), AssetType::Character));
+        }
+
+        if item.uid == ASSET_TYPE_PCAUTH {
+            return Ok((Some(item.uid.clone()), AssetType::Auth));
+        }
+
+        let Some((asset_type, name)) = item.name.split_once("::") else {
+            return Err(AssetErrors::InvalidResourceName(item.name.clone()));
+        };
+
+        Ok((Some(name.into()), AssetType::try_from(asset_type)?))
+    }
+
+    /// Returns the positions of the node and item a bookmark was generated from
+    pub fn locate(&self, bookmark: &AssetBookmark) -> Option<(usize, usize)> {
+        let node = self
+            .nodes
+            .iter()
+            .position(|node| node.node_start as usize == bookmark.node_start)?;
+        let item = self.items.iter().position(|item| {
+            item.start as usize == bookmark.node_start && item.uid == bookmark.uid
+        })?;
+
+        Some((node, item))
+    }
+
+    /// Returns the positions of the nodes not referenced by any item
+    ///
+    /// The root node, found at the lowest offset, never has an item and is
+    /// not included.
+    pub fn unmatched_nodes(&self) -> Vec<usize> {
+        let starts: HashSet<u32> = self.items.iter().map(|item| item.start).collect();
+        let root = (0..self.nodes.len()).min_by_key(|&i| self.nodes[i].node_start);
+
+        (0..self.nodes.len())
+            .filter(|&i| Some(i) != root && !starts.contains(&self.nodes[i].node_start))
+            .collect()
+    }
+
+    /// Walks the header nodes as a linked list, checking their integrity
+    pub fn node_graph(&self, database_length: u64) -> NodeGraph {
+        NodeGraph::new(&self.nodes, database_length)
+    }
+
+    /// Regenerates the list of bookmarks after modifying nodes or items
+    pub fn refresh(&mut self) -> Result<()> {
+        let (bookmarks, diagnostics) =
+            Self::read_bookmarks(&self.nodes, &self.items, &self.options)?;
+        self.bookmarks = bookmarks;
+        self.diagnostics = diagnostics;
+        self.update_lookups();
+        Ok(())
+    }
+
+    /// Sorts bookmark positions by name, uid and node start
+    fn update_lookups(&mut self) {
+        let bookmarks = &self.bookmarks;
+        self.names = NameIndex::new(bookmarks);
+
+        self.uids = (0..bookmarks.len()).collect();
+        self.uids
+            .sort_by(|&a, &b| bookmarks[a].uid.cmp(&bookmarks[b].uid));
+
+        self.starts = (0..bookmarks.len()).collect();
+        self.starts.sort_by_key(|&i| bookmarks[i].node_start);
+    }
+
+    /// Writes the index using the same layout expected by `read`
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        writer.write_u32::<LittleEndian>(self.unknown_1)?;
+        writer.write_u32::<LittleEndian>(self.unknown_2)?;
+
+        // header nodes
+        writer.write_u32::<LittleEndian>(self.nodes.len() as u32)?;
+        for node in &self.nodes {
+            node.write(&mut writer)?;
+        }
+
+        // body items
+        writer.write_u32::<LittleEndian>(self.items.len() as u32)?;
+        for item in &self.items {
+            item.write(&mut writer)?;
+        }
+
+        Ok(())
     }
 
     /// Retrieves an asset bookmark by its resource id
     pub fn get_by_resource_id(&self, resource_id: u32) -> Option<AssetBookmark> {
+        self.bookmark(resource_id).cloned()
+    }
+
+    /// Returns a reference to the bookmark with the given resource id
+    pub fn bookmark(&self, resource_id: u32) -> Option<&AssetBookmark> {
         match self
             .bookmarks
             .binary_search_by(|b| b.resource_id.cmp(&Some(resource_id)))
         {
-            Ok(pos) => Some(self.bookmarks[pos].clone()),
+            Ok(pos) => Some(&self.bookmarks[pos]),
             Err(_) => None,
         }
     }
 
+    /// Returns the bookmark with the given uid, such as `resource_50677`
+    pub fn get_by_uid(&self, uid: &str) -> Option<&AssetBookmark> {
+        let pos = self
+            .uids
+            .binary_search_by(|&i| self.bookmarks[i].uid.as_str().cmp(uid))
+            .ok()?;
+        Some(&self.bookmarks[self.uids[pos]])
+    }
+
+    /// Returns the bookmark whose block (`node_start..node_next`) contains the given database offset
+    pub fn asset_at_offset(&self, offset: u64) -> Option<&AssetBookmark> {
+        let pos = self
+            .starts
+            .partition_point(|&i| self.bookmarks[i].node_start as u64 <= offset);
+        let bookmark = &self.bookmarks[*self.starts.get(pos.checked_sub(1)?)?];
+
+        (offset < bookmark.node_next as u64).then_some(bookmark)
+    }
+
+    /// Returns the list of bookmarks, sorted by resource id
+    pub fn bookmarks(&self) -> &[AssetBookmark] {
+        &self.bookmarks
+    }
+
+    /// Returns the bookmarks with the given name
+    pub fn find_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a AssetBookmark> {
+        self.search(NamePattern::Exact(name))
+    }
+
+    /// Returns the bookmarks whose name matches the given pattern
+    pub fn search<'a>(&'a self, pattern: NamePattern) -> impl Iterator<Item = &'a AssetBookmark> {
+        self.names
+            .search(pattern, false)
+            .into_iter()
+            .map(|pos| &self.bookmarks[pos])
+    }
+
+    /// Returns the bookmarks whose name matches the given pattern, ignoring case and accents
+    pub fn search_folded<'a>(
+        &'a self,
+        pattern: NamePattern,
+    ) -> impl Iterator<Item = &'a AssetBookmark> {
+        self.names
+            .search(pattern, true)
+            .into_iter()
+            .map(|pos| &self.bookmarks[pos])
+    }
+
     /// Retrieves a list of bookmarks by their asset type
     pub fn filter_by_type(&self, asset_type: AssetType) -> Vec<AssetBookmark> {
         self.bookmarks
             .iter()
             .filter(|b| b.asset_type == asset_type)
-            .map(|b| b.clone())
+            .cloned()
             .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{AssetType, ResourceIndex};
+    use super::{AssetType, NamePattern, ResourceIndex};
+    use crate::errors::{AssetErrors, ParseOptions};
     use std::fs::File;
 
+    /// Builds a small index by hand: a root node plus a texture and a character
+    fn index_bytes() -> Vec<u8> {
+        fn node(bytes: &mut Vec<u8>, values: [u32; 5]) {
+            values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+        }
+
+        fn item(bytes: &mut Vec<u8>, uid: &str, start: u32, unknown: u32, size: u32, name: &[u8]) {
+            bytes.extend_from_slice(&(uid.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(uid.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(&start.to_le_bytes());
+            bytes.extend_from_slice(&unknown.to_le_bytes());
+            bytes.extend_from_slice(&size.to_le_bytes());
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(name);
+        }
+
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+
+        // nodes are not stored in offset order
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        node(&mut bytes, [200, 5, 300, 100, 223]);
+        node(&mut bytes, [0, 0, 100, 0, 23]);
+        node(&mut bytes, [100, 3, 200, 0, 123]);
+
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        item(
+            &mut bytes,
+            "resource_1260",
+            100,
+            7,
+            40,
+            b"TEXTURE::Pradera gris demo",
+        );
+        bytes.extend_from_slice(&[0, 0, 0xAB, 0xCD]);
+        item(&mut bytes, "resource_9", 200, 0x1234, 50, b"mesh");
+        bytes.extend_from_slice(&[6, 0]);
+        bytes.extend_from_slice(b"Ca\xf1ada");
+        bytes.push(0xEF);
+
+        bytes
+    }
+
+    #[test]
+    fn test_index_roundtrip() {
+        let bytes = index_bytes();
+        let index = ResourceIndex::read(bytes.as_slice()).unwrap();
+
+        assert_eq!(index.nodes.len(), 3);
+        assert_eq!(index.items.len(), 2);
+        assert_eq!(index.items[0].trailer, vec![0xAB, 0xCD]);
+        assert_eq!(index.items[1].char_name, Some("Cañada".into()));
+
+        let texture = index.get_by_resource_id(1260).unwrap();
+        assert_eq!(texture.asset_type, AssetType::Texture);
+        assert_eq!(texture.resource_name, "TEXTURE::Pradera gris demo");
+        assert_eq!(texture.node_start, 100);
+        assert_eq!(texture.node_previous, 0);
+        assert_eq!(texture.node_type, 3);
+        assert_eq!(texture.item_start, 100);
+        assert_eq!(texture.item_unknown, 7);
+        assert_eq!(texture.size, 40);
+
+        let character = index.get_by_resource_id(9).unwrap();
+        assert_eq!(character.asset_type, AssetType::Character);
+        assert_eq!(character.name, Some("Cañada".into()));
+        assert_eq!(character.resource_name, "mesh");
+        assert_eq!(character.node_start, 200);
+        assert_eq!(character.node_previous, 100);
+        assert_eq!(character.node_type, 5);
+        assert_eq!(character.item_start, 200);
+        assert_eq!(character.item_unknown, 0x1234);
+
+        let mut output = vec![];
+        index.write(&mut output).unwrap();
+        assert_eq!(output, bytes);
+    }
+
+    #[test]
+    fn test_index_write_overflow() {
+        let bytes = index_bytes();
+        let mut index = ResourceIndex::read(bytes.as_slice()).unwrap();
+
+        // names are stored with a 16 bit length
+        index.items[1].char_name = Some("a".repeat(0x10000));
+        let mut output = vec![];
+        assert!(matches!(
+            index.write(&mut output),
+            Err(AssetErrors::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_index_parse() {
+        let bytes = index_bytes();
+        let index = ResourceIndex::parse(&bytes).unwrap();
+
+        assert_eq!(
+            index.nodes,
+            ResourceIndex::read(bytes.as_slice()).unwrap().nodes
+        );
+        assert_eq!(index.items[1].char_name, Some("Cañada".into()));
+
+        let mut output = vec![];
+        index.write(&mut output).unwrap();
+        assert_eq!(output, bytes);
+    }
+
+    #[test]
+    fn test_index_search() {
+        let bytes = index_bytes();
+        let index = ResourceIndex::read(bytes.as_slice()).unwrap();
+
+        let found: Vec<_> = index.find_by_name("Cañada").collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].resource_id, Some(9));
+        assert_eq!(index.find_by_name("cañada").count(), 0);
+
+        let found: Vec<_> = index.search(NamePattern::Prefix("Pradera")).collect();
+        assert_eq!(found[0].resource_id, Some(1260));
+        assert_eq!(index.search(NamePattern::Glob("*gris*")).count(), 1);
+        assert_eq!(index.search(NamePattern::Glob("Pradera?gris")).count(), 0);
+
+        // case and accents are ignored
+        let found: Vec<_> = index.search_folded(NamePattern::Exact("CANADA")).collect();
+        assert_eq!(found[0].resource_id, Some(9));
+        assert_eq!(
+            index.search_folded(NamePattern::Glob("pradera*")).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_index_lookups() {
+        let bytes = index_bytes();
+        let index = ResourceIndex::read(bytes.as_slice()).unwrap();
+
+        assert_eq!(index.get_by_uid("resource_1260").unwrap().node_start, 100);
+        assert_eq!(index.get_by_uid("resource_9").unwrap().node_start, 200);
+        assert!(index.get_by_uid("resource_10").is_none());
+
+        let owner = |offset| index.asset_at_offset(offset).map(|b| b.uid.as_str());
+        assert_eq!(owner(50), None);
+        assert_eq!(owner(100), Some("resource_1260"));
+        assert_eq!(owner(199), Some("resource_1260"));
+        assert_eq!(owner(200), Some("resource_9"));
+        assert_eq!(owner(300), None);
+    }
+
+    #[test]
+    fn test_index_node_length() {
+        let mut bytes = index_bytes();
+        // end of the first node
+        bytes[12 + 16] = 200;
+
+        match ResourceIndex::read(bytes.as_slice()) {
+            Err(AssetErrors::SizeMismatch {
+                expected,
+                found,
+                offset,
+            }) => {
+                assert_eq!(expected, 23);
+                assert_eq!(found, 0);
+                assert_eq!(offset, 12);
+            }
+            _ => panic!("expected a size mismatch error"),
+        }
+    }
+
+    #[test]
+    fn test_index_missing_separator() {
+        let mut bytes = index_bytes();
+        let pos = bytes.windows(2).position(|w| w == b"::").unwrap();
+        bytes[pos..pos + 2].copy_from_slice(b"__");
+
+        match ResourceIndex::read(bytes.as_slice()) {
+            Err(AssetErrors::InvalidResourceName(name)) => {
+                assert_eq!(name, "TEXTURE__Pradera gris demo")
+            }
+            _ => panic!("expected an invalid resource name error"),
+        }
+    }
+
+    #[test]
+    fn test_index_lenient() {
+        let mut bytes = index_bytes();
+        let pos = bytes.windows(7).position(|w| w == b"TEXTURE").unwrap();
+        bytes[pos..pos + 7].copy_from_slice(b"TEXTURA");
+
+        let index =
+            ResourceIndex::read_with_options(bytes.as_slice(), ParseOptions::lenient()).unwrap();
+        assert_eq!(index.bookmarks().len(), 1);
+        assert_eq!(index.bookmarks()[0].asset_type, AssetType::Character);
+
+        // the skipped item is the first one, right after the nodes
+        assert_eq!(index.diagnostics.len(), 1);
+        assert_eq!(index.diagnostics[0].offset, 12 + 3 * 20 + 4);
+        match &index.diagnostics[0].error {
+            AssetErrors::UnknownAssetTypeError(asset_type) => assert_eq!(asset_type, "TEXTURA"),
+            _ => panic!("expected an unknown asset type error"),
+        }
+    }
+
+    #[test]
+    fn test_index_extra_node() {
+        let index = ResourceIndex::read(index_bytes().as_slice()).unwrap();
+        let mut nodes = index.nodes.clone();
+        let mut free = nodes[1].clone();
+        free.node_start = 50;
+        free.node_end = 73;
+        nodes.insert(0, free);
+
+        // the node found between the root and the texture does not shift the items
+        let index = ResourceIndex::from_parts(1, 2, nodes, index.items.clone()).unwrap();
+        assert_eq!(index.get_by_resource_id(1260).unwrap().node_start, 100);
+        assert_eq!(index.get_by_resource_id(9).unwrap().node_start, 200);
+        assert_eq!(index.unmatched_nodes(), vec![0]);
+    }
+
+    #[test]
+    fn test_index_unmatched_item() {
+        let mut bytes = index_bytes();
+        // start of the first item
+        let pos = 12 + 3 * 20 + 4 + 2 + 13 + 1;
+        bytes[pos] = 150;
+
+        match ResourceIndex::read(bytes.as_slice()) {
+            Err(AssetErrors::UnmatchedItem { uid, start }) => {
+                assert_eq!(uid, "resource_1260");
+                assert_eq!(start, 150);
+            }
+            _ => panic!("expected an unmatched item error"),
+        }
+
+        let index =
+            ResourceIndex::read_with_options(bytes.as_slice(), ParseOptions::lenient()).unwrap();
+        assert_eq!(index.bookmarks().len(), 1);
+        assert_eq!(index.diagnostics[0].offset, 12 + 3 * 20 + 4);
+        assert_eq!(index.unmatched_nodes(), vec![2]);
+
+        // the node left without an item is reported as well
+        assert_eq!(index.diagnostics.len(), 2);
+        assert_eq!(index.diagnostics[1].offset, 12 + 2 * 20);
+        match &index.diagnostics[1].error {
+            AssetErrors::UnmatchedNode { start } => assert_eq!(*start, 100),
+            _ => panic!("expected an unmatched node error"),
+        }
+    }
+
+    #[test]
+    fn test_index_shared_start() {
+        let index = ResourceIndex::read(index_bytes().as_slice()).unwrap();
+        let mut nodes = index.nodes.clone();
+        let mut copy = nodes[2].clone();
+        copy.node_type = 9;
+        nodes.push(copy);
+
+        // the item cannot tell which node it belongs to
+        match ResourceIndex::from_parts(1, 2, nodes.clone(), index.items.clone()) {
+            Err(AssetErrors::SharedNode { uid, start }) => {
+                assert_eq!(uid, "resource_1260");
+                assert_eq!(start, 100);
+            }
+            _ => panic!("expected a shared node error"),
+        }
+
+        let index = ResourceIndex::build(1, 2, nodes, index.items.clone(), ParseOptions::lenient())
+            .unwrap();
+        assert_eq!(index.bookmarks().len(), 1);
+        assert_eq!(index.diagnostics.len(), 1);
+        assert!(matches!(
+            index.diagnostics[0].error,
+            AssetErrors::SharedNode { start: 100, .. }
+        ));
+    }
+
+    #[test]
+    fn test_index_truncated() {
+        let bytes = index_bytes();
+
+        for length in 0..bytes.len() {
+            let read = match ResourceIndex::read(&bytes[..length]) {
+                Err(AssetErrors::UnexpectedEof { offset }) => offset,
+                _ => panic!("expected an unexpected eof error at length {}", length),
+            };
+            assert!(read <= length as u64);
+
+            // slices report the same offset
+            match ResourceIndex::parse(&bytes[..length]) {
+                Err(AssetErrors::UnexpectedEof { offset }) => assert_eq!(offset, read),
+                _ => panic!("expected an unexpected eof error at length {}", length),
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_unknown_type() {
+        let mut bytes = index_bytes();
+        let pos = bytes.windows(7).position(|w| w == b"TEXTURE").unwrap();
+        bytes[pos..pos + 7].copy_from_slice(b"TEXTURA");
+
+        match ResourceIndex::read(bytes.as_slice()) {
+            Err(AssetErrors::UnknownAssetTypeError(asset_type)) => {
+                assert_eq!(asset_type, "TEXTURA")
+            }
+            _ => panic!("expected an unknown asset type error"),
+        }
+    }
+
     #[test]
     fn test_index_material() {
         let f = File::open("examples/regnum/data0.idx");
@@ -1,9 +1,15 @@
-use crate::errors::AssetErrors;
-use anyhow::{anyhow, Result};
+use crate::errors::Result;
+use crate::parser::{magic, utf8_string, windows_1252, SliceResult};
+use crate::reader::OffsetReader;
+use byteorder::{LittleEndian, WriteBytesExt};
 use encoding_rs::WINDOWS_1252;
-use std::io::Read;
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u16, le_u32};
+use nom::Parser;
+use std::io::{Read, Write};
 
 /// An index item represents the data structure used to locate an asset
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResourceIndexItem {
     pub uid: String,
     pub name: String,
@@ -11,83 +17,38 @@ pub struct ResourceIndexItem {
     pub start: u32,
     pub unknown: u32,
     pub size: u32,
+    /// Bytes of unknown meaning found after the name, kept for round-tripping
+    pub trailer: Vec<u8>,
 }
 
 impl ResourceIndexItem {
-    pub fn read<T: Read>(mut reader: T) -> Result<Self> {
-        // uid length
-        let mut buffer = [0; 2];
-        reader.read(&mut buffer)?;
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u16>();
-            values
-        };
-        let [uid_length] = values[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("index item uid length"));
-        };
-
+    pub fn read<T: Read>(reader: &mut OffsetReader<T>) -> Result<Self> {
         // uid
-        let mut buffer = vec![0; uid_length.into()];
-        reader.read(&mut buffer)?;
-        let uid = String::from_utf8(buffer)?;
+        let uid_length = reader.read_u16()?;
+        let uid = reader.read_string(uid_length.into())?;
 
         // TODO: ???
-        let mut buffer = [0; 1];
-        reader.read(&mut buffer)?;
-        assert_eq!(buffer[0], 0);
+        reader.expect(&[0])?;
 
         // node start position + size
-        let mut buffer = [0; 4 * 3];
-        reader.read(&mut buffer)?;
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u32>();
-            values
-        };
-        let [start, unknown, size] = values[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("index item start/unknown/size"));
-        };
-
-        // name length
-        let mut buffer = [0; 2];
-        reader.read(&mut buffer)?;
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u16>();
-            values
-        };
-        let [name_length] = values[..] else {
-            return Err(anyhow!(AssetErrors::ParserError).context("index item name length"));
-        };
+        let start = reader.read_u32()?;
+        let unknown = reader.read_u32()?;
+        let size = reader.read_u32()?;
 
         // name
-        let mut buffer = vec![0; name_length.into()];
-        reader.read(&mut buffer)?;
-        let (name, _, _) = WINDOWS_1252.decode(&buffer);
-        let name = name.into_owned();
+        let name_length = reader.read_u16()?;
+        let name = reader.read_windows_1252(name_length.into())?;
 
         // characters.idx adds the char name here
         // play it "safe" and try to retrieve char name length
-        let mut buffer = [0; 2];
-        reader.read(&mut buffer)?;
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u16>();
-            values
-        };
-
-        let char_name = match values[0] {
+        let (char_name, trailer) = match reader.read_u16()? {
             0 => {
                 // we still need to point at the end of the next node
-                let mut buffer = [0; 2];
-                reader.read(&mut buffer)?;
-                None
+                (None, reader.read_bytes(2)?)
             }
             len => {
-                let mut buffer = vec![0; len.into()];
-                reader.read(&mut buffer)?;
-                let (char_name, _, _) = WINDOWS_1252.decode(&buffer);
-                let char_name = char_name.into_owned();
-                let mut buffer = [0; 1];
-                reader.read(&mut buffer)?;
-                Some(char_name)
+                let char_name = reader.read_windows_1252(len.into())?;
+                (Some(char_name), reader.read_bytes(1)?)
             }
         };
 
@@ -98,6 +59,94 @@ impl ResourceIndexItem {
             start,
             unknown,
             size,
+            trailer,
         })
     }
+
+    /// Parses an item from a byte slice
+    pub fn parse(input: &[u8]) -> SliceResult<'_, Self> {
+        // uid
+        let (input, uid_length) = le_u16(input)?;
+        let (input, uid) = utf8_string(uid_length.into())(input)?;
+
+        // TODO: ???
+        let (input, _) = magic(&[0])(input)?;
+
+        // node start position + size
+        let (input, (start, unknown, size)) = (le_u32, le_u32, le_u32).parse(input)?;
+
+        // name
+        let (input, name_length) = le_u16(input)?;
+        let (input, name) = windows_1252(name_length.into())(input)?;
+
+        // characters.idx adds the char name here
+        let (input, char_name_length) = le_u16(input)?;
+        let (input, char_name, trailer) = match char_name_length {
+            0 => {
+                let (input, trailer) = take(2usize)(input)?;
+                (input, None, trailer)
+            }
+            len => {
+                let (input, char_name) = windows_1252(len.into())(input)?;
+                let (input, trailer) = take(1usize)(input)?;
+                (input, Some(char_name), trailer)
+            }
+        };
+
+        Ok((
+            input,
+            Self {
+                uid,
+                name,
+                char_name,
+                start,
+                unknown,
+                size,
+                trailer: trailer.to_vec(),
+            },
+        ))
+    }
+
+    /// Returns the number of bytes used to store the item
+    pub fn length(&self) -> usize {
+        let (name, _, _) = WINDOWS_1252.encode(&self.name);
+        let char_name_length = self
+            .char_name
+            .as_ref()
+            .map_or(0, |char_name| WINDOWS_1252.encode(char_name).0.len());
+
+        2 + self.uid.len() + 1 + 4 * 3 + 2 + name.len() + 2 + char_name_length + self.trailer.len()
+    }
+
+    /// Writes the item using the same layout expected by `read`
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        // uid
+        writer.write_u16::<LittleEndian>(u16::try_from(self.uid.len())?)?;
+        writer.write_all(self.uid.as_bytes())?;
+        writer.write_u8(0)?;
+
+        // node start position + size
+        writer.write_u32::<LittleEndian>(self.start)?;
+        writer.write_u32::<LittleEndian>(self.unknown)?;
+        writer.write_u32::<LittleEndian>(self.size)?;
+
+        // name
+        let (name, _, _) = WINDOWS_1252.encode(&self.name);
+        writer.write_u16::<LittleEndian>(u16::try_from(name.len())?)?;
+        writer.write_all(&name)?;
+
+        // char name (characters.idx only)
+        match &self.char_name {
+            Some(char_name) => {
+                let (char_name, _, _) = WINDOWS_1252.encode(char_name);
+                writer.write_u16::<LittleEndian>(u16::try_from(char_name.len())?)?;
+                writer.write_all(&char_name)?;
+            }
+            None => writer.write_u16::<LittleEndian>(0)?,
+        }
+
+        writer.write_all(&self.trailer)?;
+
+        Ok(())
+    }
 }
@@ -1,5 +1,7 @@
+pub mod graph;
 pub mod index;
 pub mod item;
+pub mod names;
 pub mod node;
 
 /// An enum listing all different types of assets within a single file
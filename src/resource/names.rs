@@ -0,0 +1,182 @@
+use crate::AssetBookmark;
+
+/// An enum listing the ways an asset name can be matched
+#[derive(Debug, Clone, Copy)]
+pub enum NamePattern<'a> {
+    /// Matches the whole name
+    Exact(&'a str),
+    /// Matches names starting with the given string
+    Prefix(&'a str),
+    /// Matches names using `*` for any sequence of characters and `?` for a single one
+    Glob(&'a str),
+}
+
+/// A sorted list of asset names, used to search bookmarks by name
+pub(crate) struct NameIndex {
+    names: Vec<(String, usize)>,
+    folded: Vec<(String, usize)>,
+}
+
+impl NameIndex {
+    /// Builds the list of names from a list of bookmarks
+    pub fn new(bookmarks: &[AssetBookmark]) -> Self {
+        let mut names: Vec<(String, usize)> = bookmarks
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, b)| b.name.as_ref().map(|name| (name.clone(), pos)))
+            .collect();
+        let mut folded: Vec<(String, usize)> =
+            names.iter().map(|(name, pos)| (fold(name), *pos)).collect();
+        names.sort();
+        folded.sort();
+
+        Self { names, folded }
+    }
+
+    /// Returns the positions of the bookmarks matching the given pattern
+    ///
+    /// Folded searches ignore both case and accents.
+    pub fn search(&self, pattern: NamePattern, folded: bool) -> Vec<usize> {
+        let (names, pattern) = if folded {
+            (&self.folded, Self::fold_pattern(pattern))
+        } else {
+            (&self.names, Self::own_pattern(pattern))
+        };
+
+        match pattern {
+            OwnedPattern::Exact(name) => Self::range(names, &name)
+                .take_while(|(n, _)| *n == name)
+                .map(|(_, pos)| *pos)
+                .collect(),
+            OwnedPattern::Prefix(prefix) => Self::range(names, &prefix)
+                .take_while(|(n, _)| n.starts_with(&prefix))
+                .map(|(_, pos)| *pos)
+                .collect(),
+            OwnedPattern::Glob(pattern) => {
+                // narrow the search using the characters found before the first wildcard
+                let prefix: String = pattern.chars().take_while(|c| !"*?".contains(*c)).collect();
+                let pattern: Vec<char> = pattern.chars().collect();
+
+                Self::range(names, &prefix)
+                    .take_while(|(n, _)| n.starts_with(&prefix))
+                    .filter(|(n, _)| glob_match(&pattern, &n.chars().collect::<Vec<char>>()))
+                    .map(|(_, pos)| *pos)
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the names starting at the first one not lower than the given key
+    fn range<'a>(
+        names: &'a [(String, usize)],
+        key: &str,
+    ) -> impl Iterator<Item = &'a (String, usize)> {
+        let start = names.partition_point(|(name, _)| name.as_str() < key);
+        names[start..].iter()
+    }
+
+    fn own_pattern(pattern: NamePattern) -> OwnedPattern {
+        match pattern {
+            NamePattern::Exact(name) => OwnedPattern::Exact(name.into()),
+            NamePattern::Prefix(prefix) => OwnedPattern::Prefix(prefix.into()),
+            NamePattern::Glob(pattern) => OwnedPattern::Glob(pattern.into()),
+        }
+    }
+
+    fn fold_pattern(pattern: NamePattern) -> OwnedPattern {
+        match pattern {
+            NamePattern::Exact(name) => OwnedPattern::Exact(fold(name)),
+            NamePattern::Prefix(prefix) => OwnedPattern::Prefix(fold(prefix)),
+            NamePattern::Glob(pattern) => OwnedPattern::Glob(fold(pattern)),
+        }
+    }
+}
+
+enum OwnedPattern {
+    Exact(String),
+    Prefix(String),
+    Glob(String),
+}
+
+/// Converts a name to lowercase, removing the accents found in Windows-1252 characters
+pub fn fold(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à'..='å' => 'a',
+            'ç' => 'c',
+            'è'..='ë' => 'e',
+            'ì'..='ï' => 'i',
+            'ñ' => 'n',
+            'ò'..='ö' | 'ø' => 'o',
+            'š' => 's',
+            'ù'..='ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            'ž' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+/// Checks whether a name matches a glob pattern
+pub fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` found, along with the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` consume one more character
+                Some((star, start)) => {
+                    p = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold, glob_match};
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        glob_match(&pattern, &name)
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(
+            fold("matIgnis generales Cercas rota y vegetación"),
+            "matignis generales cercas rota y vegetacion"
+        );
+        assert_eq!(fold("CAÑADA"), "canada");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(matches("eng_npc_*", "eng_npc_template_dialog"));
+        assert!(matches("*_dialog", "eng_npc_template_dialog"));
+        assert!(matches("eng_*_template_*", "eng_npc_template_dialog"));
+        assert!(matches("combat_pain_male_?", "combat_pain_male_3"));
+        assert!(matches("*", ""));
+        assert!(!matches("combat_pain_male_?", "combat_pain_male_10"));
+        assert!(!matches("eng_*_quest", "eng_npc_template_dialog"));
+        assert!(!matches("", "loading"));
+    }
+}
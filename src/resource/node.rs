@@ -1,9 +1,12 @@
-use crate::errors::AssetErrors;
-use anyhow::{anyhow, Context, Result};
-use std::io::Read;
+use crate::errors::Result;
+use crate::parser::SliceResult;
+use crate::reader::OffsetReader;
+use byteorder::{LittleEndian, WriteBytesExt};
+use nom::{number::complete::le_u32, Parser};
+use std::io::{Read, Write};
 
 /// A wrapper struct representing a single asset node located in the resource index header
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResourceIndexNode {
     pub node_start: u32,
     pub node_type: u32,
@@ -13,18 +16,12 @@ pub struct ResourceIndexNode {
 }
 
 impl ResourceIndexNode {
-    pub fn read<T: Read>(mut reader: T) -> Result<Self> {
-        let mut buffer = [0; 4 * 5];
-        reader.read(&mut buffer)?;
-
-        let values = unsafe {
-            let (_, values, _) = buffer.align_to::<u32>();
-            values
-        };
-
-        let [node_start, node_type, node_next, node_previous, node_end] = values[..] else {
-            return Err(anyhow!(AssetErrors::ParserError)).context("index node");
-        };
+    pub fn read<T: Read>(reader: &mut OffsetReader<T>) -> Result<Self> {
+        let node_start = reader.read_u32()?;
+        let node_type = reader.read_u32()?;
+        let node_next = reader.read_u32()?;
+        let node_previous = reader.read_u32()?;
+        let node_end = reader.read_u32()?;
 
         Ok(Self {
             node_start,
@@ -34,4 +31,36 @@ impl ResourceIndexNode {
             node_end,
         })
     }
+
+    /// Parses a node from a byte slice
+    pub fn parse(input: &[u8]) -> SliceResult<'_, Self> {
+        let (input, (node_start, node_type, node_next, node_previous, node_end)) =
+            (le_u32, le_u32, le_u32, le_u32, le_u32).parse(input)?;
+
+        Ok((
+            input,
+            Self {
+                node_start,
+                node_type,
+                node_next,
+                node_previous,
+                node_end,
+            },
+        ))
+    }
+
+    /// Writes the node using the same layout expected by `read`
+    pub fn write<T: Write>(&self, mut writer: T) -> Result<()> {
+        for value in [
+            self.node_start,
+            self.node_type,
+            self.node_next,
+            self.node_previous,
+            self.node_end,
+        ] {
+            writer.write_u32::<LittleEndian>(value)?;
+        }
+
+        Ok(())
+    }
 }